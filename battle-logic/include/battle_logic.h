@@ -0,0 +1,160 @@
+/* Generated by cbindgen from src/ffi.rs, see tests/ffi.rs. */
+
+#ifndef BATTLE_LOGIC_H
+#define BATTLE_LOGIC_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum BlOutcome {
+  BL_OUTCOME_ONGOING,
+  BL_OUTCOME_ALLIES_VICTORY,
+  BL_OUTCOME_ENEMIES_VICTORY,
+  BL_OUTCOME_DRAW,
+  /**
+   * The engine failed while playing the fight, which can't go on.
+   */
+  BL_OUTCOME_ERROR,
+} BlOutcome;
+
+typedef enum BlEventKind {
+  BL_EVENT_KIND_TURN_STARTED,
+  BL_EVENT_KIND_RULE_FIRED,
+  BL_EVENT_KIND_ACTION_CHOSEN,
+  BL_EVENT_KIND_REACTED,
+  BL_EVENT_KIND_ACTION_FAILED,
+  BL_EVENT_KIND_STAT_CHANGED,
+  BL_EVENT_KIND_STATUS_APPLIED,
+  BL_EVENT_KIND_STATUS_TICKED,
+  BL_EVENT_KIND_STATUS_EXPIRED,
+  BL_EVENT_KIND_DIED,
+  BL_EVENT_KIND_OUTCOME,
+} BlEventKind;
+
+/**
+ * A fight and the events it produced.
+ */
+typedef struct BlFight BlFight;
+
+/**
+ * A [FightEvent] flattened for C. Fields that don't apply to the kind are zero.
+ */
+typedef struct BlEvent {
+  enum BlEventKind kind;
+  /**
+   * Letter of the fighter, as in fight_status logs.
+   */
+  char fighter;
+  /**
+   * Letter of the target of an action.
+   */
+  char target;
+  /**
+   * Turn, rule index, relic index, status duration or remaining turns, or [BlOutcome].
+   */
+  uint32_t number;
+  /**
+   * Stat before a change.
+   */
+  uint16_t before;
+  /**
+   * Stat after a change, or status potency.
+   */
+  uint16_t after;
+  /**
+   * Rune of the action, stat or status, e.g. `ATK`, `HP` or `POISON`; empty if none.
+   */
+  const char *rune;
+} BlEvent;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a fight from a fighters_info text. On error, including a failure of the engine, returns null
+ * and, if `error` isn't null, stores the message there.
+ *
+ * # Safety
+ *
+ * `fighters_info` must be a NUL-terminated string, and `error` null or valid for writes.
+ */
+struct BlFight *bl_fight_new(const char *fighters_info,
+                             char **error);
+
+/**
+ * # Safety
+ *
+ * `fight` must come from [bl_fight_new] and not be used afterwards.
+ */
+void bl_fight_free(struct BlFight *fight);
+
+/**
+ * Plays the action of the next fighter. Returns [BlOutcome::Error] if `fight` is null or the engine failed.
+ *
+ * # Safety
+ *
+ * `fight` must come from [bl_fight_new].
+ */
+enum BlOutcome bl_fight_step(struct BlFight *fight);
+
+/**
+ * Plays until the end of the fight. Returns [BlOutcome::Error] if `fight` is null or the engine failed.
+ *
+ * # Safety
+ *
+ * `fight` must come from [bl_fight_new].
+ */
+enum BlOutcome bl_fight_run(struct BlFight *fight);
+
+/**
+ * Number of events kept since the last [bl_fight_clear_events].
+ *
+ * # Safety
+ *
+ * `fight` must come from [bl_fight_new].
+ */
+size_t bl_fight_event_count(const struct BlFight *fight);
+
+/**
+ * Reads the event at `index` into `event`, returns false if there is none or the engine failed.
+ *
+ * # Safety
+ *
+ * `fight` must come from [bl_fight_new], and `event` be valid for writes.
+ */
+bool bl_fight_event(const struct BlFight *fight, size_t index, struct BlEvent *event);
+
+/**
+ * Kept events in the fight_status format, to free with [bl_string_free]. Null if the engine failed.
+ *
+ * # Safety
+ *
+ * `fight` must come from [bl_fight_new].
+ */
+char *bl_fight_events_text(const struct BlFight *fight);
+
+/**
+ * Forgets the kept events.
+ *
+ * # Safety
+ *
+ * `fight` must come from [bl_fight_new].
+ */
+void bl_fight_clear_events(struct BlFight *fight);
+
+/**
+ * # Safety
+ *
+ * `string` must come from this API and not be used afterwards.
+ */
+void bl_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BATTLE_LOGIC_H */
@@ -0,0 +1,280 @@
+use std::fmt::{Display, Formatter};
+
+use crate::logic::prelude::*;
+use crate::predefined::prelude::{get_spell, get_weapon};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Issue {
+    /// Earlier rules always fire first, whatever the turn.
+    Unreachable,
+    AlwaysTrue,
+    AlwaysFalse,
+    /// The condition can't be met during a fight of at most the given number of turns.
+    ImpossibleTurn(Condition, u8),
+    /// `EXT 0`: no turn is a multiple of zero.
+    DivisionByZero,
+    /// No such weapon exists, or the fighter doesn't carry it.
+    MissingWeapon(WeaponRef),
+    /// No such spell exists, or the fighter doesn't carry it.
+    MissingSpell(SpellRef),
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::AlwaysTrue => Severity::Info,
+            Issue::Unreachable | Issue::AlwaysFalse | Issue::ImpossibleTurn(..) => Severity::Warning,
+            Issue::DivisionByZero | Issue::MissingWeapon(_) | Issue::MissingSpell(_) => Severity::Error,
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::Unreachable => write!(f, "unreachable, earlier rules always fire first"),
+            Issue::AlwaysTrue => write!(f, "the gate is always true"),
+            Issue::AlwaysFalse => write!(f, "the gate is never true"),
+            Issue::ImpossibleTurn(condition, max_turns) => {
+                write!(f, "{} can't happen within {} turns", condition, max_turns)
+            }
+            Issue::DivisionByZero => write!(f, "EXT 0 never fires"),
+            Issue::MissingWeapon(weapon) => write!(f, "no {} to attack with", weapon),
+            Issue::MissingSpell(spell) => write!(f, "no {} to cast", spell),
+        }
+    }
+}
+
+/// An issue found in the rule at index `rule`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub rule: usize,
+    pub severity: Severity,
+    pub issue: Issue,
+}
+
+impl Diagnostic {
+    fn new(rule: usize, issue: Issue) -> Self {
+        Diagnostic { rule, severity: issue.severity(), issue }
+    }
+}
+
+/// Analyzes a rule list without running any fight, for fights of at most `max_turns` turns.
+/// Weapons are checked against the predefined ones.
+pub fn analyze(rules: &[Rule], max_turns: u8) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    // For each turn, whether a previous rule is sure to fire.
+    let mut covered = vec![false; max_turns as usize];
+
+    for (index, rule) in rules.iter().enumerate() {
+        for condition in rule.gate.conditions() {
+            match condition {
+                Condition::EveryXTurn(0) => diagnostics.push(Diagnostic::new(index, Issue::DivisionByZero)),
+                Condition::EveryXTurn(turn) | Condition::OnTurn(turn) | Condition::FromTurnX(turn)
+                    if *turn > max_turns =>
+                {
+                    diagnostics.push(Diagnostic::new(index, Issue::ImpossibleTurn(condition.clone(), max_turns)))
+                }
+                Condition::OnTurn(0) => {
+                    diagnostics.push(Diagnostic::new(index, Issue::ImpossibleTurn(condition.clone(), max_turns)))
+                }
+                _ => {}
+            }
+        }
+
+        let values = (1..=max_turns).map(|turn| rule.gate.eval(turn)).collect::<Vec<Option<bool>>>();
+        if values.iter().all(|v| *v == Some(true)) {
+            diagnostics.push(Diagnostic::new(index, Issue::AlwaysTrue));
+        } else if values.iter().all(|v| *v == Some(false)) {
+            diagnostics.push(Diagnostic::new(index, Issue::AlwaysFalse));
+        }
+        let reachable = values.iter().zip(covered.iter()).any(|(v, c)| !c && *v != Some(false));
+        if !reachable && values.iter().any(|v| *v != Some(false)) {
+            diagnostics.push(Diagnostic::new(index, Issue::Unreachable));
+        }
+        for (covered, value) in covered.iter_mut().zip(values) {
+            *covered |= value == Some(true);
+        }
+
+        match &rule.action {
+            Action::Attack(_, reference) if get_weapon(reference.kind, reference.level).is_none() => {
+                diagnostics.push(Diagnostic::new(index, Issue::MissingWeapon(*reference)))
+            }
+            Action::Spell(_, reference) if get_spell(reference.kind, reference.level).is_none() => {
+                diagnostics.push(Diagnostic::new(index, Issue::MissingSpell(*reference)))
+            }
+            _ => {}
+        }
+    }
+    diagnostics
+}
+
+/// Analyzes the rules of a fighter, weapons and spells being checked against the ones it carries.
+pub fn analyze_fighter(fighter: &Fighter, max_turns: u8) -> Vec<Diagnostic> {
+    let mut diagnostics = analyze(fighter.get_rules(), max_turns);
+    diagnostics.retain(|d| !matches!(d.issue, Issue::MissingWeapon(_) | Issue::MissingSpell(_)));
+    for (index, rule) in fighter.get_rules().iter().enumerate() {
+        match &rule.action {
+            Action::Attack(_, reference) if fighter.get_weapon(reference).is_none() => {
+                diagnostics.push(Diagnostic::new(index, Issue::MissingWeapon(*reference)))
+            }
+            Action::Spell(_, reference)
+                if !fighter.get_spell().is_some_and(|s| (s.get_kind(), s.get_level()) == (reference.kind, reference.level)) =>
+            {
+                diagnostics.push(Diagnostic::new(index, Issue::MissingSpell(*reference)))
+            }
+            _ => {}
+        }
+    }
+    diagnostics.sort_by_key(|d| d.rule);
+    diagnostics
+}
+
+impl Gate {
+    /// All the conditions of the gate, from left to right.
+    fn conditions(&self) -> Vec<&Condition> {
+        match self {
+            Gate::ID(condition) => vec![condition],
+            Gate::NOT(gate) => gate.conditions(),
+            Gate::AND(g1, g2) | Gate::NAND(g1, g2) | Gate::OR(g1, g2)
+            | Gate::XOR(g1, g2) | Gate::NOR(g1, g2) | Gate::NXOR(g1, g2) => {
+                let mut conditions = g1.conditions();
+                conditions.extend(g2.conditions());
+                conditions
+            }
+        }
+    }
+
+    /// Value of the gate on the given turn, `None` when it depends on the fighters.
+    fn eval(&self, turn: u8) -> Option<bool> {
+        let and = |g1: &Gate, g2: &Gate| match (g1.eval(turn), g2.eval(turn)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        };
+        let or = |g1: &Gate, g2: &Gate| match (g1.eval(turn), g2.eval(turn)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        };
+        let xor = |g1: &Gate, g2: &Gate| Some(g1.eval(turn)? != g2.eval(turn)?);
+        match self {
+            Gate::ID(condition) => condition.eval(turn),
+            Gate::NOT(gate) => gate.eval(turn).map(|v| !v),
+            Gate::AND(g1, g2) => and(g1, g2),
+            Gate::NAND(g1, g2) => and(g1, g2).map(|v| !v),
+            Gate::OR(g1, g2) => or(g1, g2),
+            Gate::NOR(g1, g2) => or(g1, g2).map(|v| !v),
+            Gate::XOR(g1, g2) => xor(g1, g2),
+            Gate::NXOR(g1, g2) => xor(g1, g2).map(|v| !v),
+        }
+    }
+}
+
+impl Condition {
+    fn eval(&self, turn: u8) -> Option<bool> {
+        match self {
+            Condition::EveryXTurn(x) => Some(turn.is_multiple_of(*x)),
+            Condition::OnTurn(x) => Some(turn == *x),
+            Condition::FromTurnX(x) => Some(turn >= *x),
+            Condition::LessXHP(0, _) => Some(false),
+            Condition::MoreXHP(x, _) if *x >= 100 => Some(false),
+            Condition::LessXHP(..) | Condition::MoreXHP(..) | Condition::HasStatus(..) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::analyzer::*;
+    use crate::predefined::prelude::*;
+
+    fn rule(gate: impl Into<Gate>) -> Rule {
+        Rule { gate: gate.into(), action: Action::Wait }
+    }
+
+    #[test]
+    fn test_analyze() {
+        let low = Condition::LessXHP(30, Target::Them);
+        let rules = vec![
+            rule(Gate::and(Condition::EveryXTurn(2), low.clone())),
+            rule(Condition::EveryXTurn(0)),
+            rule(Condition::OnTurn(60)),
+            rule(Gate::or(Condition::EveryXTurn(2), Gate::not(Condition::EveryXTurn(2)))),
+            rule(low),
+        ];
+        assert_eq!(analyze(&rules, MAX_TURNS), vec![
+            Diagnostic { rule: 1, severity: Severity::Error, issue: Issue::DivisionByZero },
+            Diagnostic { rule: 1, severity: Severity::Warning, issue: Issue::AlwaysFalse },
+            Diagnostic { rule: 2, severity: Severity::Warning, issue: Issue::ImpossibleTurn(Condition::OnTurn(60), MAX_TURNS) },
+            Diagnostic { rule: 2, severity: Severity::Warning, issue: Issue::AlwaysFalse },
+            Diagnostic { rule: 3, severity: Severity::Info, issue: Issue::AlwaysTrue },
+            Diagnostic { rule: 4, severity: Severity::Warning, issue: Issue::Unreachable },
+        ]);
+
+        // Odd and even turns together cover the whole fight.
+        let rules = vec![
+            rule(Condition::EveryXTurn(2)),
+            rule(Gate::not(Condition::EveryXTurn(2))),
+            rule(Condition::HasStatus(Target::Them, Status::Burn)),
+        ];
+        assert_eq!(analyze(&rules, MAX_TURNS), vec![
+            Diagnostic { rule: 2, severity: Severity::Warning, issue: Issue::Unreachable },
+        ]);
+        assert_eq!(analyze(&[Rules::Careful.new(), Rules::Attack2.new()], MAX_TURNS), vec![]);
+
+        // The turn limit of the fight decides which turns can happen.
+        let rules = vec![rule(Condition::OnTurn(20))];
+        assert_eq!(analyze(&rules, 30), vec![]);
+        let diagnostics = analyze(&rules, 10);
+        assert_eq!(diagnostics[0].issue, Issue::ImpossibleTurn(Condition::OnTurn(20), 10));
+        assert_eq!(diagnostics[0].issue.to_string(), "T= 20 can't happen within 10 turns");
+        assert_eq!(diagnostics[1].issue, Issue::AlwaysFalse);
+    }
+
+    #[test]
+    fn test_missing_equipment() {
+        let iron = WeaponRef::new(WeaponKind::Sword, 2);
+        let unknown = WeaponRef::new(WeaponKind::Sword, 9);
+        let rules = vec![
+            Rule { gate: Gate::ID(Condition::OnTurn(1)), action: Action::Attack(Target::Them, unknown) },
+            Rule { gate: Gate::ID(Condition::OnTurn(2)), action: Action::Attack(Target::Them, iron) },
+            Rules::Attack2.new(),
+        ];
+        assert_eq!(analyze(&rules, MAX_TURNS), vec![
+            Diagnostic { rule: 0, severity: Severity::Error, issue: Issue::MissingWeapon(unknown) },
+        ]);
+
+        let mut arches = Fighters::Arches.new();
+        arches.set_rules(rules);
+        assert_eq!(analyze_fighter(&arches, MAX_TURNS), vec![
+            Diagnostic { rule: 0, severity: Severity::Error, issue: Issue::MissingWeapon(unknown) },
+            Diagnostic { rule: 1, severity: Severity::Error, issue: Issue::MissingWeapon(iron) },
+        ]);
+
+        let fireball = SpellRef::new(SpellKind::Fireball, 1);
+        let rules = vec![Rule { gate: Gate::ID(Condition::OnTurn(1)), action: Action::Spell(Target::Them, fireball) }];
+        assert_eq!(analyze(&rules, MAX_TURNS), vec![]);
+        arches.set_rules(rules);
+        assert_eq!(analyze_fighter(&arches, MAX_TURNS), vec![
+            Diagnostic { rule: 0, severity: Severity::Error, issue: Issue::MissingSpell(fireball) },
+        ]);
+    }
+}
@@ -0,0 +1,346 @@
+//! Plain-language explanation of rules, e.g. "Every 2 turns, if my HP is below 30%, defend".
+
+use crate::logic::prelude::{Action, Condition, Gate, Rule, SpellKind, Stat, Status, Target, WeaponKind};
+
+/// Phrases of one language. Gates are explained by combining them,
+/// negations being pushed down to the conditions.
+pub trait Phrasebook {
+    fn condition(&self, condition: &Condition, negated: bool) -> String;
+    fn action(&self, action: &Action) -> String;
+    fn and(&self, a: String, b: String) -> String;
+    fn or(&self, a: String, b: String) -> String;
+    fn xor(&self, a: String, b: String) -> String;
+    fn nxor(&self, a: String, b: String) -> String;
+    /// Sets apart an operand that combines conditions itself, so that nested gates read unambiguously.
+    fn group(&self, operand: String) -> String;
+    /// Assembles the sentence.
+    fn rule(&self, gate: String, action: String) -> String;
+}
+
+pub fn explain(rule: &Rule, phrases: &dyn Phrasebook) -> String {
+    phrases.rule(explain_gate(&rule.gate, false, phrases), phrases.action(&rule.action))
+}
+
+fn explain_gate(gate: &Gate, negated: bool, phrases: &dyn Phrasebook) -> String {
+    let operand = |gate: &Gate, negated: bool| {
+        let phrase = explain_gate(gate, negated, phrases);
+        if is_compound(gate) { phrases.group(phrase) } else { phrase }
+    };
+    let both = |g1: &Gate, g2: &Gate, negated: bool| (operand(g1, negated), operand(g2, negated));
+    // not (a and b) = not a or not b, not (a or b) = not a and not b
+    let and = |g1: &Gate, g2: &Gate, negated: bool| {
+        let (a, b) = both(g1, g2, negated);
+        if negated { phrases.or(a, b) } else { phrases.and(a, b) }
+    };
+    let or = |g1: &Gate, g2: &Gate, negated: bool| {
+        let (a, b) = both(g1, g2, negated);
+        if negated { phrases.and(a, b) } else { phrases.or(a, b) }
+    };
+    let xor = |g1: &Gate, g2: &Gate, negated: bool| {
+        let (a, b) = both(g1, g2, false);
+        if negated { phrases.nxor(a, b) } else { phrases.xor(a, b) }
+    };
+    match gate {
+        Gate::ID(condition) => phrases.condition(condition, negated),
+        Gate::NOT(gate) => explain_gate(gate, !negated, phrases),
+        Gate::AND(g1, g2) => and(g1, g2, negated),
+        Gate::NAND(g1, g2) => and(g1, g2, !negated),
+        Gate::OR(g1, g2) => or(g1, g2, negated),
+        Gate::NOR(g1, g2) => or(g1, g2, !negated),
+        Gate::XOR(g1, g2) => xor(g1, g2, negated),
+        Gate::NXOR(g1, g2) => xor(g1, g2, !negated),
+    }
+}
+
+/// Whether a gate combines several conditions, negations aside.
+fn is_compound(gate: &Gate) -> bool {
+    match gate {
+        Gate::ID(_) => false,
+        Gate::NOT(gate) => is_compound(gate),
+        _ => true,
+    }
+}
+
+fn capitalize(sentence: &str) -> String {
+    let mut chars = sentence.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub struct English;
+
+impl English {
+    fn stat(stat: &Stat) -> &'static str {
+        match stat {
+            Stat::Health => "health",
+            Stat::Attack => "attack",
+            Stat::Defense => "defense",
+            Stat::Speed => "speed",
+            Stat::Nature => "nature",
+            Stat::Demon => "demon",
+        }
+    }
+
+    fn target(target: &Target) -> String {
+        match target {
+            Target::Them => "me".to_string(),
+            Target::FoeLess(stat) => format!("the foe with the lowest {}", English::stat(stat)),
+            Target::FoeMost(stat) => format!("the foe with the highest {}", English::stat(stat)),
+            Target::AllyLess(stat) => format!("the ally with the lowest {}", English::stat(stat)),
+            Target::AllyMost(stat) => format!("the ally with the highest {}", English::stat(stat)),
+        }
+    }
+
+    fn possessive(target: &Target) -> String {
+        match target {
+            Target::Them => "my".to_string(),
+            _ => format!("{}'s", English::target(target)),
+        }
+    }
+}
+
+impl Phrasebook for English {
+    fn condition(&self, condition: &Condition, negated: bool) -> String {
+        let not = if negated { " not" } else { "" };
+        match condition {
+            Condition::EveryXTurn(1) if !negated => "every turn".to_string(),
+            Condition::EveryXTurn(1) => "never".to_string(),
+            Condition::EveryXTurn(0) if !negated => "never".to_string(),
+            Condition::EveryXTurn(0) => "every turn".to_string(),
+            Condition::EveryXTurn(n) if !negated => format!("every {} turns", n),
+            Condition::EveryXTurn(n) => format!("except every {} turns", n),
+            Condition::OnTurn(n) if !negated => format!("on turn {}", n),
+            Condition::OnTurn(n) => format!("except on turn {}", n),
+            Condition::FromTurnX(n) if !negated => format!("from turn {}", n),
+            Condition::FromTurnX(n) => format!("before turn {}", n),
+            Condition::LessXHP(n, target) => {
+                format!("if {} HP is{} below {}%", English::possessive(target), not, n)
+            }
+            Condition::MoreXHP(n, target) => {
+                format!("if {} HP is{} above {}%", English::possessive(target), not, n)
+            }
+            Condition::HasStatus(target, status) => {
+                let status = match status {
+                    Status::Poisoned => "poisoned",
+                    Status::Slow => "slowed",
+                    Status::Burn => "burning",
+                };
+                match target {
+                    Target::Them => format!("if I am{} {}", not, status),
+                    _ => format!("if {} is{} {}", English::target(target), not, status),
+                }
+            }
+        }
+    }
+
+    fn action(&self, action: &Action) -> String {
+        let object = |target: &Target| match target {
+            Target::Them => "myself".to_string(),
+            _ => English::target(target),
+        };
+        match action {
+            Action::Attack(target, weapon) => {
+                let name = match weapon.kind {
+                    WeaponKind::Sword => "sword",
+                };
+                format!("attack {} with my level {} {}", object(target), weapon.level, name)
+            }
+            Action::Spell(target, spell) => {
+                let name = match spell.kind {
+                    SpellKind::Fireball => "fireball",
+                };
+                format!("cast a level {} {} on {}", spell.level, name, object(target))
+            }
+            Action::Defense => "defend".to_string(),
+            Action::Wait => "wait".to_string(),
+        }
+    }
+
+    fn and(&self, a: String, b: String) -> String {
+        format!("{}, {}", a, b)
+    }
+
+    fn or(&self, a: String, b: String) -> String {
+        format!("either {} or {}", a, b)
+    }
+
+    fn xor(&self, a: String, b: String) -> String {
+        format!("either {} or {} but not both", a, b)
+    }
+
+    fn nxor(&self, a: String, b: String) -> String {
+        format!("{} exactly when {}", a, b)
+    }
+
+    fn group(&self, operand: String) -> String {
+        format!("({})", operand)
+    }
+
+    fn rule(&self, gate: String, action: String) -> String {
+        capitalize(&format!("{}, {}", gate, action))
+    }
+}
+
+pub struct French;
+
+impl French {
+    fn stat(stat: &Stat) -> &'static str {
+        match stat {
+            Stat::Health => "de vie",
+            Stat::Attack => "d'attaque",
+            Stat::Defense => "de défense",
+            Stat::Speed => "de vitesse",
+            Stat::Nature => "de nature",
+            Stat::Demon => "de démon",
+        }
+    }
+
+    fn target(target: &Target) -> String {
+        match target {
+            Target::Them => "moi".to_string(),
+            Target::FoeLess(stat) => format!("l'ennemi ayant le moins {}", French::stat(stat)),
+            Target::FoeMost(stat) => format!("l'ennemi ayant le plus {}", French::stat(stat)),
+            Target::AllyLess(stat) => format!("l'allié ayant le moins {}", French::stat(stat)),
+            Target::AllyMost(stat) => format!("l'allié ayant le plus {}", French::stat(stat)),
+        }
+    }
+
+    fn health(target: &Target) -> String {
+        match target {
+            Target::Them => "mes PV".to_string(),
+            _ => format!("les PV de {}", French::target(target)),
+        }
+    }
+}
+
+impl Phrasebook for French {
+    fn condition(&self, condition: &Condition, negated: bool) -> String {
+        let (ne, pas) = if negated { ("ne ", " pas") } else { ("", "") };
+        match condition {
+            Condition::EveryXTurn(1) if !negated => "à chaque tour".to_string(),
+            Condition::EveryXTurn(1) => "jamais".to_string(),
+            Condition::EveryXTurn(0) if !negated => "jamais".to_string(),
+            Condition::EveryXTurn(0) => "à chaque tour".to_string(),
+            Condition::EveryXTurn(n) if !negated => format!("tous les {} tours", n),
+            Condition::EveryXTurn(n) => format!("sauf tous les {} tours", n),
+            Condition::OnTurn(n) if !negated => format!("au tour {}", n),
+            Condition::OnTurn(n) => format!("sauf au tour {}", n),
+            Condition::FromTurnX(n) if !negated => format!("à partir du tour {}", n),
+            Condition::FromTurnX(n) => format!("avant le tour {}", n),
+            Condition::LessXHP(n, target) => {
+                format!("si {} {}sont{} sous {} %", French::health(target), ne, pas, n)
+            }
+            Condition::MoreXHP(n, target) => {
+                format!("si {} {}sont{} au-dessus de {} %", French::health(target), ne, pas, n)
+            }
+            Condition::HasStatus(target, status) => {
+                let status = match status {
+                    Status::Poisoned => "empoisonné",
+                    Status::Slow => "ralenti",
+                    Status::Burn => "brûlé",
+                };
+                match target {
+                    Target::Them if negated => format!("si je ne suis pas {}", status),
+                    Target::Them => format!("si je suis {}", status),
+                    _ => format!("si {} {}est{} {}", French::target(target), ne, pas, status),
+                }
+            }
+        }
+    }
+
+    fn action(&self, action: &Action) -> String {
+        match action {
+            Action::Attack(target, weapon) => {
+                let name = match weapon.kind {
+                    WeaponKind::Sword => "mon épée",
+                };
+                match target {
+                    Target::Them => format!("m'attaquer avec {} de niveau {}", name, weapon.level),
+                    _ => format!("attaquer {} avec {} de niveau {}", French::target(target), name, weapon.level),
+                }
+            }
+            Action::Spell(target, spell) => {
+                let name = match spell.kind {
+                    SpellKind::Fireball => "une boule de feu",
+                };
+                format!("lancer {} de niveau {} sur {}", name, spell.level, French::target(target))
+            }
+            Action::Defense => "se défendre".to_string(),
+            Action::Wait => "attendre".to_string(),
+        }
+    }
+
+    fn and(&self, a: String, b: String) -> String {
+        format!("{}, {}", a, b)
+    }
+
+    fn or(&self, a: String, b: String) -> String {
+        format!("soit {}, soit {}", a, b)
+    }
+
+    fn xor(&self, a: String, b: String) -> String {
+        format!("soit {}, soit {}, mais pas les deux", a, b)
+    }
+
+    fn nxor(&self, a: String, b: String) -> String {
+        format!("{} exactement quand {}", a, b)
+    }
+
+    fn group(&self, operand: String) -> String {
+        format!("({})", operand)
+    }
+
+    fn rule(&self, gate: String, action: String) -> String {
+        capitalize(&format!("{}, {}", gate, action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::explain::*;
+    use crate::api::input::read_rule;
+
+    #[test]
+    fn test_explain() {
+        let explain_rule = |rule: &str, phrases: &dyn Phrasebook| explain(&read_rule(rule).unwrap(), phrases);
+
+        assert_eq!(explain_rule("AND EXT 2 HP< 30 SLF DEF", &English),
+                   "Every 2 turns, if my HP is below 30%, defend");
+        assert_eq!(explain_rule("AND EXT 2 HP< 30 SLF DEF", &French),
+                   "Tous les 2 tours, si mes PV sont sous 30 %, se défendre");
+
+        assert_eq!(explain_rule("NOR T= 1 STATUS EM SPD SLOW ATK EL HP SWORD 2", &English),
+                   "Except on turn 1, if the foe with the highest speed is not slowed, \
+                    attack the foe with the lowest health with my level 2 sword");
+        assert_eq!(explain_rule("NAND T>= 3 MXHP 50 AM ATK SPL SELF FIREBALL 1", &English),
+                   "Either before turn 3 or if the ally with the highest attack's HP is not above 50%, \
+                    cast a level 1 fireball on myself");
+        assert_eq!(explain_rule("XOR EXT 1 NOT STATUS SELF BURN WAIT", &English),
+                   "Either every turn or if I am not burning but not both, wait");
+        assert_eq!(explain_rule("NOT NXOR EXT 3 STATUS SELF POISON WAIT", &French),
+                   "Soit tous les 3 tours, soit si je suis empoisonné, mais pas les deux, attendre");
+        assert_eq!(explain_rule("NOT OR EXT 1 LXHP 10 AL DEF ATK SELF SWORD 1", &French),
+                   "Jamais, si les PV de l'allié ayant le moins de défense ne sont pas sous 10 %, \
+                    m'attaquer avec mon épée de niveau 1");
+
+        // Nested gates are grouped, so that (a and b) or c reads differently from a and (b or c).
+        assert_eq!(explain_rule("OR AND EXT 2 HP< 30 SLF T= 3 DEF", &English),
+                   "Either (every 2 turns, if my HP is below 30%) or on turn 3, defend");
+        assert_eq!(explain_rule("AND EXT 2 OR HP< 30 SLF T= 3 DEF", &English),
+                   "Every 2 turns, (either if my HP is below 30% or on turn 3), defend");
+        assert_eq!(explain_rule("OR AND EXT 2 HP< 30 SLF T= 3 DEF", &French),
+                   "Soit (tous les 2 tours, si mes PV sont sous 30 %), soit au tour 3, se défendre");
+        assert_eq!(explain_rule("AND EXT 2 OR HP< 30 SLF T= 3 DEF", &French),
+                   "Tous les 2 tours, (soit si mes PV sont sous 30 %, soit au tour 3), se défendre");
+        assert_eq!(explain_rule("NAND NOT OR T= 1 T= 2 EXT 3 WAIT", &English),
+                   "Either (either on turn 1 or on turn 2) or except every 3 turns, wait");
+
+        // EXT 0 never fires.
+        assert_eq!(explain_rule("ID EXT 0 DEF", &English), "Never, defend");
+        assert_eq!(explain_rule("NOT EXT 0 DEF", &English), "Every turn, defend");
+        assert_eq!(explain_rule("ID EXT 0 DEF", &French), "Jamais, se défendre");
+        assert_eq!(explain_rule("NOT EXT 0 DEF", &French), "À chaque tour, se défendre");
+    }
+}
@@ -1,5 +1,8 @@
+use std::fmt;
 use std::str::FromStr;
-use crate::logic::prelude::{Action, Condition, Gate, Rule, Stat, Target};
+
+use crate::api::runes::{self, RuneCategory, RuneDef};
+use crate::logic::prelude::{Action, Condition, Gate, Rule, SpellKind, SpellRef, Stat, Status, Target, WeaponKind, WeaponRef};
 
 pub enum Rune {
     Gate(Gate),
@@ -7,125 +10,223 @@ pub enum Rune {
     Action(Action),
     Target(Target),
     Stat(Stat),
+    Status(Status),
+    Weapon(WeaponRef),
+    Spell(SpellRef),
     Number(u8),
 }
 
-fn read_rule(rule: &str) -> Option<Rule> {
-    let mut runes = rule.split(" ").collect::<Vec<&str>>();
-    runes.reverse();
-
-    let Some(Rune::Gate(gate)) = read_gate(&mut runes) else { return None; };
-    let Some(Rune::Action(action)) = read_action(&mut runes) else { return None; };
-
-    if !runes.is_empty() { return None }
-
-    return Some(Rule { gate, action });
+/// Rune that could not be read. `line` and `position` start at 1,
+/// `position` counting runes and not characters.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RuneError {
+    pub line: usize,
+    pub position: usize,
+    pub expected: RuneCategory,
+    /// `None` when the rule ended too early.
+    pub found: Option<String>,
+    /// Keywords close to the one found, closest first.
+    pub suggestions: Vec<&'static str>,
 }
 
-fn read_gate(rule: &mut Vec<&str>) -> Option<Rune> {
-    let Some(rune) = rule.pop() else { return None; };
-    match rune {
-        "ID" | "NOT" => {
-            let Some(Rune::Condition(condition)) = read_condition(rule) else { return None; };
-            match rune {
-                "ID" => Some(Rune::Gate(Gate::ID(condition))),
-                "NOT" => Some(Rune::Gate(Gate::NOT(condition))),
-                _ => None,
-            }
+impl RuneError {
+    /// Describes the error without its location.
+    pub fn message(&self) -> String {
+        let mut message = format!("expected {}", self.expected);
+        match &self.found {
+            Some(found) => message += &format!(", found {}", found),
+            None => message += ", found nothing",
         }
-        _ => {
-            let Some(Rune::Condition(c1)) = read_condition(rule) else { return None; };
-            let Some(Rune::Condition(c2)) = read_condition(rule) else { return None; };
-            match rune {
-                "AND" => Some(Rune::Gate(Gate::AND(c1, c2))),
-                "NAND" => Some(Rune::Gate(Gate::NAND(c1, c2))),
-                "OR" => Some(Rune::Gate(Gate::OR(c1, c2))),
-                "XOR" => Some(Rune::Gate(Gate::XOR(c1, c2))),
-                "NOR" => Some(Rune::Gate(Gate::NOR(c1, c2))),
-                "NXOR" => Some(Rune::Gate(Gate::NXOR(c1, c2))),
-                _ => None,
-            }
+        if !self.suggestions.is_empty() {
+            message += &format!(" (did you mean {}?)", self.suggestions.join(" or "));
         }
+        message
+    }
+
+    /// Column (starting at 1) of the rune in `rule`, the line it was read from,
+    /// or the column after the rule when it ended too early.
+    pub fn column(&self, rule: &str) -> usize {
+        let chars = rule.chars().collect::<Vec<char>>();
+        let start = (0..chars.len())
+            .filter(|&i| !chars[i].is_whitespace() && (i == 0 || chars[i - 1].is_whitespace()))
+            .nth(self.position - 1);
+        start.unwrap_or(rule.trim_end().chars().count()) + 1
     }
 }
 
-fn read_condition(rule: &mut Vec<&str>) -> Option<Rune> {
-    let Some(rune) = rule.pop() else { return None; };
-    match rune {
-        "EXT" | "T=" | "T>" => {
-            let Some(Rune::Number(n)) = read_number(rule) else { return None; };
-            match rune {
-                "EXT" => Some(Rune::Condition(Condition::EveryXTurn(n))),
-                "T=" => Some(Rune::Condition(Condition::OnTurn(n))),
-                "T>" => Some(Rune::Condition(Condition::FromTurnX(n))),
-                _ => None
-            }
-        }
-        "HP>" | "HP<" => {
-            let Some(Rune::Number(n)) = read_number(rule) else { return None; };
-            let Some(Rune::Target(t)) = read_target(rule) else { return None; };
-            match rune {
-                "HP>" => Some(Rune::Condition(Condition::MoreXHP(n, t))),
-                "HP<" => Some(Rune::Condition(Condition::LessXHP(n, t))),
-                _ => None
-            }
-        }
-        "STA" => {
-            // TODO
-            None
-        }
-        _ => None
+impl fmt::Display for RuneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.position, self.message())
     }
 }
 
-fn read_action(rule: &mut Vec<&str>) -> Option<Rune> {
-    let Some(rune) = rule.pop() else { return None; };
-    match rune {
-        "ATK" => match read_target(rule) {
-            Some(Rune::Target(t)) => Some(Rune::Action(Action::Attack(t))),
-            _ => None,
+impl std::error::Error for RuneError {}
+
+/// Runes of a rule, read from left to right.
+struct Runes<'a> {
+    runes: Vec<&'a str>,
+    line: usize,
+    position: usize,
+}
+
+impl<'a> Runes<'a> {
+    fn new(rule: &'a str, line: usize) -> Self {
+        Runes { runes: rule.split_whitespace().collect(), line, position: 0 }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rune = self.runes.get(self.position).copied();
+        self.position += 1;
+        rune
+    }
+
+    /// Reads a keyword of the expected category.
+    fn expect(&mut self, expected: RuneCategory) -> Result<&'static RuneDef, RuneError> {
+        match self.next() {
+            Some(word) => runes::find(expected, word).ok_or_else(|| self.error(expected)),
+            None => Err(self.error(expected)),
         }
-        "DEF" => Some(Rune::Action(Action::Defense)),
-        "W" => Some(Rune::Action(Action::Wait)),
-        _ => None,
+    }
+
+    /// Error about the last rune read.
+    fn error(&self, expected: RuneCategory) -> RuneError {
+        let found = self.runes.get(self.position - 1).map(|rune| rune.to_string());
+        let suggestions = match &found {
+            Some(found) => suggest(found, expected),
+            None => vec![],
+        };
+        RuneError { line: self.line, position: self.position, expected, found, suggestions }
     }
 }
 
-fn read_target(rule: &mut Vec<&str>) -> Option<Rune> {
-    let Some(rune) = rule.pop() else { return None; };
-    match rune {
-        "SLF" => Some(Rune::Target(Target::Them)),
-        _ => {
-            let Some(Rune::Stat(s)) = read_stat(rule) else { return None; };
-            match rune {
-                "AL+" => Some(Rune::Target(Target::AllyMost(s))),
-                "AL-" => Some(Rune::Target(Target::AllyLess(s))),
-                "FO+" => Some(Rune::Target(Target::FoeMost(s))),
-                "FO-" => Some(Rune::Target(Target::FoeLess(s))),
-                _ => None,
+/// Spellings a misspelled rune could have been meant as, one per rune.
+fn suggest(found: &str, expected: RuneCategory) -> Vec<&'static str> {
+    let found = found.to_uppercase();
+    let mut suggestions = runes::runes(expected)
+        .filter_map(|rune| {
+            rune.spellings()
+                .map(|spelling| (distance(&found, spelling), spelling))
+                .filter(|(distance, spelling)| *distance <= if spelling.len() <= 4 { 1 } else { 2 })
+                .min()
+        })
+        .collect::<Vec<(usize, &'static str)>>();
+    suggestions.sort_by_key(|(distance, _)| *distance);
+    suggestions.dedup_by_key(|(_, spelling)| *spelling);
+    suggestions.into_iter().map(|(_, spelling)| spelling).collect()
+}
+
+/// Edit distance between two words, swapping two neighbouring letters counting as one edit.
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<char>>();
+    let b = b.chars().collect::<Vec<char>>();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
             }
         }
     }
+    d[a.len()][b.len()]
+}
+
+/// Reads a rule written as runes, e.g. `AND LXHP 30 SELF EXT 2 DEF`.
+pub fn read_rule(rule: &str) -> Result<Rule, RuneError> {
+    read_line(rule, 1)
 }
 
-fn read_stat(rule: &mut Vec<&str>) -> Option<Rune> {
-    let Some(rune) = rule.pop() else { return None; };
-    match rune {
-        "HP" => Some(Rune::Stat(Stat::Health)),
-        "ATK" => Some(Rune::Stat(Stat::Attack)),
-        "DEF" => Some(Rune::Stat(Stat::Defense)),
-        "SPD" => Some(Rune::Stat(Stat::Speed)),
-        "NAT" => Some(Rune::Stat(Stat::Nature)),
-        "DEM" => Some(Rune::Stat(Stat::Demon)),
-        _ => None,
+/// Reads one rule per line, skipping empty lines.
+pub fn read_rules(rules: &str) -> Result<Vec<Rule>, RuneError> {
+    rules
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| read_line(line, index + 1))
+        .collect()
+}
+
+fn read_line(rule: &str, line: usize) -> Result<Rule, RuneError> {
+    let mut runes = Runes::new(rule, line);
+
+    let Rune::Gate(gate) = read(&mut runes, RuneCategory::Gate)? else { unreachable!() };
+    let Rune::Action(action) = read(&mut runes, RuneCategory::Action)? else { unreachable!() };
+
+    if runes.next().is_some() {
+        return Err(runes.error(RuneCategory::End));
     }
+
+    Ok(Rule { gate, action })
 }
 
-fn read_number(rule: &mut Vec<&str>) -> Option<Rune> {
-    let Some(rune) = rule.pop() else { return None; };
-    match u8::from_str(rune) {
-        Ok(n) => Some(Rune::Number(n)),
-        _ => None,
+/// Reads a rune of the expected category, followed by its arguments.
+fn read(rule: &mut Runes, expected: RuneCategory) -> Result<Rune, RuneError> {
+    if expected == RuneCategory::Number {
+        return match rule.next().map(u8::from_str) {
+            Some(Ok(n)) => Ok(Rune::Number(n)),
+            _ => Err(rule.error(RuneCategory::Number)),
+        };
+    }
+
+    let rune = rule.expect(expected)?;
+    let arguments = rune.arguments
+        .iter()
+        .map(|category| read(rule, *category))
+        .collect::<Result<Vec<Rune>, RuneError>>()?;
+    Ok(build(rune, arguments))
+}
+
+/// Builds a rune from its arguments, which match the categories of the rune table.
+fn build(rune: &RuneDef, arguments: Vec<Rune>) -> Rune {
+    use Rune as R;
+    let operand = |rune: &Rune| match rune {
+        R::Gate(gate) => gate.clone(),
+        R::Condition(condition) => Gate::ID(condition.clone()),
+        _ => unreachable!(),
+    };
+    match (rune.category, rune.keyword, arguments.as_slice()) {
+        (RuneCategory::Gate, "ID", [R::Condition(c)]) => R::Gate(Gate::ID(c.clone())),
+        (RuneCategory::Gate, "NOT", [g]) => R::Gate(Gate::not(operand(g))),
+        (RuneCategory::Gate, "AND", [g1, g2]) => R::Gate(Gate::and(operand(g1), operand(g2))),
+        (RuneCategory::Gate, "NAND", [g1, g2]) => R::Gate(Gate::nand(operand(g1), operand(g2))),
+        (RuneCategory::Gate, "OR", [g1, g2]) => R::Gate(Gate::or(operand(g1), operand(g2))),
+        (RuneCategory::Gate, "XOR", [g1, g2]) => R::Gate(Gate::xor(operand(g1), operand(g2))),
+        (RuneCategory::Gate, "NOR", [g1, g2]) => R::Gate(Gate::nor(operand(g1), operand(g2))),
+        (RuneCategory::Gate, "NXOR", [g1, g2]) => R::Gate(Gate::nxor(operand(g1), operand(g2))),
+        (RuneCategory::Condition, "EXT", [R::Number(n)]) => R::Condition(Condition::EveryXTurn(*n)),
+        (RuneCategory::Condition, "T=", [R::Number(n)]) => R::Condition(Condition::OnTurn(*n)),
+        (RuneCategory::Condition, "T>=", [R::Number(n)]) => R::Condition(Condition::FromTurnX(*n)),
+        (RuneCategory::Condition, "LXHP", [R::Number(n), R::Target(t)]) => R::Condition(Condition::LessXHP(*n, t.clone())),
+        (RuneCategory::Condition, "MXHP", [R::Number(n), R::Target(t)]) => R::Condition(Condition::MoreXHP(*n, t.clone())),
+        (RuneCategory::Condition, "STATUS", [R::Target(t), R::Status(s)]) => R::Condition(Condition::HasStatus(t.clone(), *s)),
+        (RuneCategory::Action, "ATK", [R::Target(t), R::Weapon(w)]) => R::Action(Action::Attack(t.clone(), *w)),
+        (RuneCategory::Action, "SPL", [R::Target(t), R::Spell(s)]) => R::Action(Action::Spell(t.clone(), *s)),
+        (RuneCategory::Action, "DEF", []) => R::Action(Action::Defense),
+        (RuneCategory::Action, "WAIT", []) => R::Action(Action::Wait),
+        (RuneCategory::Target, "SELF", []) => R::Target(Target::Them),
+        (RuneCategory::Target, "EL", [R::Stat(s)]) => R::Target(Target::FoeLess(s.clone())),
+        (RuneCategory::Target, "EM", [R::Stat(s)]) => R::Target(Target::FoeMost(s.clone())),
+        (RuneCategory::Target, "AL", [R::Stat(s)]) => R::Target(Target::AllyLess(s.clone())),
+        (RuneCategory::Target, "AM", [R::Stat(s)]) => R::Target(Target::AllyMost(s.clone())),
+        (RuneCategory::Stat, "HP", []) => R::Stat(Stat::Health),
+        (RuneCategory::Stat, "ATK", []) => R::Stat(Stat::Attack),
+        (RuneCategory::Stat, "DEF", []) => R::Stat(Stat::Defense),
+        (RuneCategory::Stat, "NAT", []) => R::Stat(Stat::Nature),
+        (RuneCategory::Stat, "DEM", []) => R::Stat(Stat::Demon),
+        (RuneCategory::Stat, "SPD", []) => R::Stat(Stat::Speed),
+        (RuneCategory::Status, "POISON", []) => R::Status(Status::Poisoned),
+        (RuneCategory::Status, "SLOW", []) => R::Status(Status::Slow),
+        (RuneCategory::Status, "BURN", []) => R::Status(Status::Burn),
+        (RuneCategory::Weapon, "SWORD", [R::Number(n)]) => R::Weapon(WeaponRef::new(WeaponKind::Sword, *n)),
+        (RuneCategory::Spell, "FIREBALL", [R::Number(n)]) => R::Spell(SpellRef::new(SpellKind::Fireball, *n)),
+        _ => unreachable!("{} doesn't match the rune table", rune.keyword),
     }
 }
 
@@ -135,24 +236,24 @@ fn test() {
     let c2 = Condition::EveryXTurn(3);
     for (id, gate) in [
         ("ID", Gate::ID(c1.clone())),
-        ("NOT", Gate::NOT(c1.clone()))
+        ("NOT", Gate::not(c1.clone()))
     ] {
-        assert_eq!(read_rule(id), None);
+        assert_eq!(read_rule(id).ok(), None);
         assert_eq!(read_rule(&format!("{} EXT 2 W", id)),
-                   Some(Rule { gate, action: Action::Wait, }));
+                   Ok(Rule { gate, action: Action::Wait, }));
     }
 
     for (id, gate) in [
-        ("AND", Gate::AND(c1.clone(), c2.clone())),
-        ("NAND", Gate::NAND(c1.clone(), c2.clone())),
-        ("OR", Gate::OR(c1.clone(), c2.clone())),
-        ("XOR", Gate::XOR(c1.clone(), c2.clone())),
-        ("NOR", Gate::NOR(c1.clone(), c2.clone())),
-        ("NXOR", Gate::NXOR(c1.clone(), c2.clone())),
+        ("AND", Gate::and(c1.clone(), c2.clone())),
+        ("NAND", Gate::nand(c1.clone(), c2.clone())),
+        ("OR", Gate::or(c1.clone(), c2.clone())),
+        ("XOR", Gate::xor(c1.clone(), c2.clone())),
+        ("NOR", Gate::nor(c1.clone(), c2.clone())),
+        ("NXOR", Gate::nxor(c1.clone(), c2.clone())),
     ] {
-        assert_eq!(read_rule(id), None);
+        assert_eq!(read_rule(id).ok(), None);
         assert_eq!(read_rule(&format!("{} EXT 2 EXT 3 W", id)),
-                   Some(Rule { gate, action: Action::Wait, }));
+                   Ok(Rule { gate, action: Action::Wait, }));
     }
 
     for (id, cond) in [
@@ -161,18 +262,65 @@ fn test() {
         ("T> 2", Condition::FromTurnX(2)),
         ("HP> 10 SLF", Condition::MoreXHP(10, Target::Them)),
         ("HP< 10 SLF", Condition::LessXHP(10, Target::Them)),
+        ("STA SLF SLOW", Condition::HasStatus(Target::Them, Status::Slow)),
+        ("STA FO+ HP BURN", Condition::HasStatus(Target::FoeMost(Stat::Health), Status::Burn)),
     ] {
-        assert_eq!(read_rule(&format!("ID {}", id)), None);
+        assert_eq!(read_rule(&format!("ID {}", id)).ok(), None);
         assert_eq!(read_rule(&format!("ID {} W", id)),
-                   Some(Rule { gate: Gate::ID(cond), action: Action::Wait }));
+                   Ok(Rule { gate: Gate::ID(cond), action: Action::Wait }));
     }
 
+    assert_eq!(read_rule("AND HP< 30 SLF OR EXT 2 NOT T= 1 DEF"),
+               Ok(Rule {
+                   gate: Gate::and(
+                       Condition::LessXHP(30, Target::Them),
+                       Gate::or(Condition::EveryXTurn(2), Gate::not(Condition::OnTurn(1))),
+                   ),
+                   action: Action::Defense,
+               }));
+    assert_eq!(read_rule("AND EXT 2 OR EXT 3 DEF").ok(), None);
+
+    // Canonical spellings and aliases are interchangeable.
+    assert_eq!(read_rule("AND LXHP 30 SELF OR T>= 2 STATUS AM SPD SLOW WAIT"),
+               read_rule("AND HP< 30 SLF OR T> 2 STA AL+ SPD SLOW W"));
+    assert_eq!(read_rule("ID MXHP 50 EL HP ATK EM DEF SWORD 1"),
+               read_rule("ID HP> 50 FO- HP ATK FO+ DEF SWORD 1"));
+
     for (id, action) in [
-        ("ATK SLF", Action::Attack(Target::Them)),
+        ("ATK SLF SWORD 2", Action::Attack(Target::Them, WeaponRef::new(WeaponKind::Sword, 2))),
+        ("SPL FO- DEM FIREBALL 1", Action::Spell(Target::FoeLess(Stat::Demon), SpellRef::new(SpellKind::Fireball, 1))),
         ("DEF", Action::Defense),
         ("W", Action::Wait),
     ] {
         assert_eq!(read_rule(&format!("ID EXT 1 {}", id)),
-                   Some(Rule { gate: Gate::ID(Condition::EveryXTurn(1)), action }))
+                   Ok(Rule { gate: Gate::ID(Condition::EveryXTurn(1)), action }))
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_errors() {
+    let error = |line, position, expected, found: Option<&str>, suggestions| RuneError {
+        line, position, expected, found: found.map(str::to_string), suggestions,
+    };
+    assert_eq!(read_rule("ID EXT 2 ATK FO HP SWORD 1"),
+               Err(error(1, 5, RuneCategory::Target, Some("FO"), vec!["FO-", "FO+"])));
+    assert_eq!(read_rule("AD EXT 2 DEF"),
+               Err(error(1, 1, RuneCategory::Gate, Some("AD"), vec!["ID", "AND"])));
+    assert_eq!(read_rule("AND EXT 2 NTO T= 1 DEF"),
+               Err(error(1, 4, RuneCategory::Operand, Some("NTO"), vec!["NOT"])));
+    assert_eq!(read_rule("ID STA SLF burn W"),
+               Err(error(1, 4, RuneCategory::Status, Some("burn"), vec!["BURN"])));
+    assert_eq!(read_rule("ID EXT two W"),
+               Err(error(1, 3, RuneCategory::Number, Some("two"), vec![])));
+    assert_eq!(read_rule("ID EXT 2"),
+               Err(error(1, 4, RuneCategory::Action, None, vec![])));
+    assert_eq!(read_rule("ID EXT 2 W W"),
+               Err(error(1, 5, RuneCategory::End, Some("W"), vec![])));
+    assert_eq!(read_rule("ID EXT 2 ATK FO HP SWORD 1").unwrap_err().to_string(),
+               "1:5: expected a target, found FO (did you mean FO- or FO+?)");
+    assert_eq!(read_rule("ID EXT 2 ATK FO HP SWORD 1").unwrap_err().column("ID EXT 2 ATK FO HP SWORD 1"), 14);
+    assert_eq!(read_rule("  ID  EXT 2").unwrap_err().column("  ID  EXT 2"), 12);
+
+    assert_eq!(read_rules("ID EXT 2 DEF\n\nID EXT 1 W\n").unwrap().len(), 2);
+    assert_eq!(read_rules("ID EXT 2 DEF\n\nID EXT 1 WIAT\n"),
+               Err(error(3, 4, RuneCategory::Action, Some("WIAT"), vec!["WAIT"])));
+}
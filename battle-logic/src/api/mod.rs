@@ -1 +1,7 @@
-pub mod input;
\ No newline at end of file
+pub mod analyzer;
+pub mod explain;
+pub mod input;
+pub mod output;
+pub mod protocol;
+pub mod runes;
+pub mod verifier;
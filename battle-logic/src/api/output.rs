@@ -0,0 +1,156 @@
+//! Canonical rune text of rules, read back by [crate::api::input::read_rule].
+
+use std::fmt::{Display, Formatter, Result};
+
+use crate::logic::prelude::{Action, Condition, Gate, Rule, SpellKind, SpellRef, Stat, Status, Target, WeaponKind, WeaponRef};
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{} {}", self.gate, self.action)
+    }
+}
+
+impl Display for Gate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Gate::ID(c) => write!(f, "ID {}", c),
+            Gate::NOT(g) => write!(f, "NOT {}", Operand(g)),
+            Gate::AND(g1, g2) => write!(f, "AND {} {}", Operand(g1), Operand(g2)),
+            Gate::NAND(g1, g2) => write!(f, "NAND {} {}", Operand(g1), Operand(g2)),
+            Gate::OR(g1, g2) => write!(f, "OR {} {}", Operand(g1), Operand(g2)),
+            Gate::XOR(g1, g2) => write!(f, "XOR {} {}", Operand(g1), Operand(g2)),
+            Gate::NOR(g1, g2) => write!(f, "NOR {} {}", Operand(g1), Operand(g2)),
+            Gate::NXOR(g1, g2) => write!(f, "NXOR {} {}", Operand(g1), Operand(g2)),
+        }
+    }
+}
+
+/// Operands that are plain conditions are written without their `ID`.
+struct Operand<'a>(&'a Gate);
+
+impl Display for Operand<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.0 {
+            Gate::ID(c) => write!(f, "{}", c),
+            gate => write!(f, "{}", gate),
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Condition::EveryXTurn(n) => write!(f, "EXT {}", n),
+            Condition::OnTurn(n) => write!(f, "T= {}", n),
+            Condition::FromTurnX(n) => write!(f, "T>= {}", n),
+            Condition::LessXHP(n, target) => write!(f, "LXHP {} {}", n, target),
+            Condition::MoreXHP(n, target) => write!(f, "MXHP {} {}", n, target),
+            Condition::HasStatus(target, status) => write!(f, "STATUS {} {}", target, status),
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Action::Attack(target, weapon) => write!(f, "ATK {} {}", target, weapon),
+            Action::Spell(target, spell) => write!(f, "SPL {} {}", target, spell),
+            Action::Defense => write!(f, "DEF"),
+            Action::Wait => write!(f, "WAIT"),
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Target::Them => write!(f, "SELF"),
+            Target::FoeLess(stat) => write!(f, "EL {}", stat),
+            Target::FoeMost(stat) => write!(f, "EM {}", stat),
+            Target::AllyLess(stat) => write!(f, "AL {}", stat),
+            Target::AllyMost(stat) => write!(f, "AM {}", stat),
+        }
+    }
+}
+
+impl Display for Stat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(match self {
+            Stat::Health => "HP",
+            Stat::Attack => "ATK",
+            Stat::Defense => "DEF",
+            Stat::Nature => "NAT",
+            Stat::Demon => "DEM",
+            Stat::Speed => "SPD",
+        })
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(match self {
+            Status::Poisoned => "POISON",
+            Status::Slow => "SLOW",
+            Status::Burn => "BURN",
+        })
+    }
+}
+
+impl Display for WeaponRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.kind {
+            WeaponKind::Sword => write!(f, "SWORD {}", self.level),
+        }
+    }
+}
+
+impl Display for SpellRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.kind {
+            SpellKind::Fireball => write!(f, "FIREBALL {}", self.level),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::input::read_rule;
+    use crate::logic::prelude::*;
+
+    #[test]
+    fn test_round_trip() {
+        let c1 = Condition::EveryXTurn(2);
+        let c2 = Condition::LessXHP(30, Target::Them);
+        let gates = vec![
+            Gate::ID(c1.clone()),
+            Gate::not(c1.clone()),
+            Gate::and(c1.clone(), c2.clone()),
+            Gate::nand(c1.clone(), Gate::not(c2.clone())),
+            Gate::or(Gate::xor(c1.clone(), c2.clone()), c2.clone()),
+            Gate::nor(c1.clone(), Gate::nxor(c2.clone(), Gate::ID(c1.clone()))),
+            Gate::ID(Condition::OnTurn(3)),
+            Gate::ID(Condition::FromTurnX(4)),
+            Gate::ID(Condition::MoreXHP(50, Target::AllyMost(Stat::Speed))),
+            Gate::ID(Condition::HasStatus(Target::AllyLess(Stat::Nature), Status::Poisoned)),
+            Gate::ID(Condition::HasStatus(Target::FoeMost(Stat::Attack), Status::Slow)),
+            Gate::ID(Condition::HasStatus(Target::FoeLess(Stat::Defense), Status::Burn)),
+            Gate::ID(Condition::LessXHP(10, Target::FoeLess(Stat::Demon))),
+        ];
+        let actions = vec![
+            Action::Attack(Target::FoeLess(Stat::Health), WeaponRef::new(WeaponKind::Sword, 1)),
+            Action::Spell(Target::AllyMost(Stat::Demon), SpellRef::new(SpellKind::Fireball, 2)),
+            Action::Defense,
+            Action::Wait,
+        ];
+
+        for gate in &gates {
+            for action in &actions {
+                let rule = Rule { gate: gate.clone(), action: action.clone() };
+                assert_eq!(read_rule(&rule.to_string()), Ok(rule));
+            }
+        }
+
+        let rule = Rule { gate: gates[5].clone(), action: actions[0].clone() };
+        assert_eq!(rule.to_string(), "NOR EXT 2 NXOR LXHP 30 SELF EXT 2 ATK EL HP SWORD 1");
+    }
+}
@@ -0,0 +1,270 @@
+//! JSON-lines protocol to drive fights from another process, see [serve].
+//!
+//! Each request is a JSON object on one line, e.g. `{"version": 1, "id": 3, "type": "step"}`.
+//! Replies carry the same `version` and `id`: the events of the fight, one per line,
+//! then the result of the request, or an `error` reply.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::grammar::fighters_info::read_fighters;
+use crate::logic::prelude::*;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Loads both teams, replacing the current fight.
+    Load {
+        fighters_info: String,
+        #[serde(default)]
+        turns: Option<u8>,
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+    /// Plays until the end of the fight.
+    Run,
+    /// Plays the action of the next fighter.
+    Step,
+    Fighter {
+        fighter: FighterID,
+    },
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Loaded {
+        fighters: Vec<(FighterID, String)>,
+    },
+    Event {
+        event: FightEvent,
+    },
+    Ran {
+        outcome: State,
+    },
+    Stepped {
+        turn: u8,
+        outcome: Option<State>,
+    },
+    Fighter {
+        fighter: FighterState,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FighterState {
+    pub id: FighterID,
+    pub name: String,
+    pub alive: bool,
+    pub max_health: u16,
+    /// Current stats, by rune name.
+    pub stats: BTreeMap<String, u16>,
+    pub statuses: Vec<ActiveStatus>,
+    pub modifiers: Vec<Modifier>,
+}
+
+impl FighterState {
+    pub fn new(id: FighterID, fighter: &Fighter) -> Self {
+        FighterState {
+            id,
+            name: fighter.get_name().clone(),
+            alive: fighter.is_alive(),
+            max_health: fighter.get_max_health(),
+            stats: Stat::ALL.iter().map(|stat| (stat.to_string(), fighter.get_stat(stat))).collect(),
+            statuses: fighter.get_statuses().clone(),
+            modifiers: fighter.get_modifiers().clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Reply<'a> {
+    version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a Value>,
+    #[serde(flatten)]
+    response: Response,
+}
+
+/// Fight driven by requests.
+#[derive(Default)]
+pub struct Session {
+    fight: Option<Fight>,
+}
+
+impl Session {
+    /// Handles one request, returning the reply lines.
+    pub fn handle(&mut self, line: &str) -> Vec<String> {
+        let (id, responses) = match serde_json::from_str::<Value>(line) {
+            Ok(message) => {
+                let id = message.get("id").cloned();
+                (id, self.handle_message(message))
+            }
+            Err(error) => (None, vec![error_reply(format!("invalid JSON: {}", error))]),
+        };
+        responses
+            .into_iter()
+            .map(|response| {
+                serde_json::to_string(&Reply { version: PROTOCOL_VERSION, id: id.as_ref(), response }).unwrap()
+            })
+            .collect()
+    }
+
+    fn handle_message(&mut self, message: Value) -> Vec<Response> {
+        match message.get("version").and_then(Value::as_u64) {
+            Some(version) if version == PROTOCOL_VERSION as u64 => {}
+            Some(version) => {
+                return vec![error_reply(format!("unsupported version {}, expected {}", version, PROTOCOL_VERSION))];
+            }
+            None => return vec![error_reply("missing version".to_string())],
+        }
+        let request = match Request::deserialize(message) {
+            Ok(request) => request,
+            Err(error) => return vec![error_reply(format!("invalid request: {}", error))],
+        };
+
+        match panic::catch_unwind(AssertUnwindSafe(|| self.respond(request))) {
+            Ok(Ok(responses)) => responses,
+            Ok(Err(message)) => vec![error_reply(message)],
+            Err(_) => {
+                self.fight = None;
+                vec![error_reply("internal error, the fight was dropped".to_string())]
+            }
+        }
+    }
+
+    fn respond(&mut self, request: Request) -> Result<Vec<Response>, String> {
+        if let Request::Load { fighters_info, turns, seed } = request {
+            if turns == Some(0) {
+                return Err("invalid turn limit: 0, expected 1 to 255".to_string());
+            }
+            let (allies, enemies) = read_fighters(&fighters_info).map_err(|error| error.to_string())?;
+            let mut fight = Fight::build_fight(allies, enemies);
+            if let Some(turns) = turns {
+                fight = fight.with_max_turns(turns);
+            }
+            if let Some(seed) = seed {
+                fight = fight.with_seed(seed);
+            }
+            let fighters = fight.fighters.iter().map(|(id, f)| (*id, f.borrow().get_name().clone())).collect();
+            self.fight = Some(fight);
+            return Ok(vec![Response::Loaded { fighters }]);
+        }
+
+        let fight = self.fight.as_mut().ok_or("no fight loaded")?;
+        let mut events: Vec<FightEvent> = Vec::new();
+        let result = match request {
+            Request::Run => Response::Ran { outcome: fight.run(&mut events) },
+            Request::Step => {
+                let outcome = fight.step(&mut events);
+                Response::Stepped { turn: fight.turn, outcome }
+            }
+            Request::Fighter { fighter } => {
+                let state = fight.fighter(fighter).map(|f| FighterState::new(fighter, &f));
+                Response::Fighter { fighter: state.ok_or(format!("no fighter {:?}", fighter))? }
+            }
+            Request::Load { .. } => unreachable!(),
+        };
+
+        let mut responses = events.into_iter().map(|event| Response::Event { event }).collect::<Vec<Response>>();
+        responses.push(result);
+        Ok(responses)
+    }
+}
+
+fn error_reply(message: String) -> Response {
+    Response::Error { message }
+}
+
+/// Answers requests read line by line until the end of `input`.
+pub fn serve(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut session = Session::default();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        for reply in session.handle(&line) {
+            writeln!(output, "{}", reply)?;
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::api::protocol::*;
+
+    fn request(session: &mut Session, request: Value) -> Vec<Value> {
+        session.handle(&request.to_string()).iter().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_session() {
+        let info = include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap();
+        let mut session = Session::default();
+
+        let replies = request(&mut session, json!({ "version": 1, "id": 1, "type": "step" }));
+        assert_eq!(replies, vec![json!({ "version": 1, "id": 1, "type": "error", "message": "no fight loaded" })]);
+
+        let replies = request(&mut session, json!({ "version": 1, "type": "load", "fighters_info": info }));
+        assert_eq!(replies, vec![json!({
+            "version": 1, "type": "loaded", "fighters": [[{ "Ally": 0 }, "HERO"], [{ "Enemy": 0 }, "ENEMY"]],
+        })]);
+
+        // HERO is faster and defends first.
+        let replies = request(&mut session, json!({ "version": 1, "id": "a", "type": "step" }));
+        assert_eq!(replies[0], json!({ "version": 1, "id": "a", "type": "event", "event": { "TurnStarted": { "turn": 1 } } }));
+        assert_eq!(replies.last().unwrap(), &json!({ "version": 1, "id": "a", "type": "stepped", "turn": 1, "outcome": null }));
+
+        let replies = request(&mut session, json!({ "version": 1, "type": "fighter", "fighter": { "Enemy": 0 } }));
+        assert_eq!(replies[0]["fighter"]["name"], "ENEMY");
+        assert_eq!(replies[0]["fighter"]["stats"]["HP"], 10);
+        assert_eq!(replies[0]["fighter"]["alive"], true);
+
+        let replies = request(&mut session, json!({ "version": 1, "type": "run" }));
+        assert_eq!(replies.last().unwrap(), &json!({ "version": 1, "type": "ran", "outcome": "EnemiesVictory" }));
+        let replies = request(&mut session, json!({ "version": 1, "type": "step" }));
+        assert_eq!(replies, vec![json!({ "version": 1, "type": "stepped", "turn": 1, "outcome": "EnemiesVictory" })]);
+
+        for (message, error) in [
+            (json!({ "version": 2, "type": "run" }), "unsupported version 2, expected 1"),
+            (json!({ "type": "run" }), "missing version"),
+            (json!({ "version": 1, "type": "fighter", "fighter": { "Ally": 3 } }), "no fighter Ally(3)"),
+            (json!({ "version": 1, "type": "load", "fighters_info": "[A] HERO\n" }), "2:1: expected stats"),
+            (json!({ "version": 1, "type": "load", "fighters_info": info, "turns": 0 }), "invalid turn limit: 0, expected 1 to 255"),
+        ] {
+            assert_eq!(request(&mut session, message)[0]["message"], error);
+        }
+        assert!(request(&mut session, json!({ "version": 1, "type": "dance" }))[0]["message"]
+            .as_str().unwrap().starts_with("invalid request"));
+        assert!(session.handle("{")[0].contains("invalid JSON"));
+    }
+
+    #[test]
+    fn test_serve() {
+        let info = include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap();
+        let input = format!(
+            "{}\n\n{}\n",
+            json!({ "version": 1, "type": "load", "fighters_info": info }),
+            json!({ "version": 1, "type": "run" }),
+        );
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.lines().all(|line| serde_json::from_str::<Value>(line).is_ok()));
+        assert!(output.ends_with("{\"version\":1,\"type\":\"ran\",\"outcome\":\"EnemiesVictory\"}\n"));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+use std::iter;
+
+/// What a rune is read as.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RuneCategory {
+    Gate,
+    /// Operand of a gate: either a gate or a condition.
+    Operand,
+    Condition,
+    Action,
+    Target,
+    Stat,
+    Status,
+    Weapon,
+    Spell,
+    Number,
+    /// Nothing, the rule is complete.
+    End,
+}
+
+impl RuneCategory {
+    /// Whether a rune of the given category can be read where `self` is expected.
+    pub fn accepts(&self, category: RuneCategory) -> bool {
+        *self == category
+            || (*self == RuneCategory::Operand && matches!(category, RuneCategory::Gate | RuneCategory::Condition))
+    }
+}
+
+impl fmt::Display for RuneCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RuneCategory::Gate => "a gate",
+            RuneCategory::Operand => "a gate or a condition",
+            RuneCategory::Condition => "a condition",
+            RuneCategory::Action => "an action",
+            RuneCategory::Target => "a target",
+            RuneCategory::Stat => "a stat",
+            RuneCategory::Status => "a status",
+            RuneCategory::Weapon => "a weapon",
+            RuneCategory::Spell => "a spell",
+            RuneCategory::Number => "a number",
+            RuneCategory::End => "the end of the rule",
+        })
+    }
+}
+
+/// A keyword of the rune language.
+#[derive(Debug, PartialEq)]
+pub struct RuneDef {
+    /// Spelling used when writing rules.
+    pub keyword: &'static str,
+    /// Other accepted spellings.
+    pub aliases: &'static [&'static str],
+    pub category: RuneCategory,
+    /// Categories of the runes following this one.
+    pub arguments: &'static [RuneCategory],
+}
+
+impl RuneDef {
+    pub fn arity(&self) -> usize {
+        self.arguments.len()
+    }
+
+    pub fn spellings(&self) -> impl Iterator<Item = &'static str> {
+        iter::once(self.keyword).chain(self.aliases.iter().copied())
+    }
+}
+
+const fn rune(
+    keyword: &'static str,
+    aliases: &'static [&'static str],
+    category: RuneCategory,
+    arguments: &'static [RuneCategory],
+) -> RuneDef {
+    RuneDef { keyword, aliases, category, arguments }
+}
+
+use RuneCategory::{Action, Condition, Gate, Number, Operand, Spell, Stat, Status, Target, Weapon};
+
+/// Every keyword of the rune language, read by [crate::api::input] and the fighters_info loader.
+pub const RUNES: &[RuneDef] = &[
+    rune("ID", &[], Gate, &[Condition]),
+    rune("NOT", &[], Gate, &[Operand]),
+    rune("AND", &[], Gate, &[Operand, Operand]),
+    rune("NAND", &[], Gate, &[Operand, Operand]),
+    rune("OR", &[], Gate, &[Operand, Operand]),
+    rune("XOR", &[], Gate, &[Operand, Operand]),
+    rune("NOR", &[], Gate, &[Operand, Operand]),
+    rune("NXOR", &[], Gate, &[Operand, Operand]),
+    rune("EXT", &[], Condition, &[Number]),
+    rune("T=", &[], Condition, &[Number]),
+    rune("T>=", &["T>"], Condition, &[Number]),
+    rune("LXHP", &["HP<"], Condition, &[Number, Target]),
+    rune("MXHP", &["HP>"], Condition, &[Number, Target]),
+    rune("STATUS", &["STA"], Condition, &[Target, Status]),
+    rune("ATK", &[], Action, &[Target, Weapon]),
+    rune("SPL", &[], Action, &[Target, Spell]),
+    rune("DEF", &[], Action, &[]),
+    rune("WAIT", &["W"], Action, &[]),
+    rune("SELF", &["SLF"], Target, &[]),
+    rune("EL", &["FO-"], Target, &[Stat]),
+    rune("EM", &["FO+"], Target, &[Stat]),
+    rune("AL", &["AL-"], Target, &[Stat]),
+    rune("AM", &["AL+"], Target, &[Stat]),
+    rune("HP", &[], Stat, &[]),
+    rune("ATK", &[], Stat, &[]),
+    rune("DEF", &[], Stat, &[]),
+    rune("NAT", &[], Stat, &[]),
+    rune("DEM", &[], Stat, &[]),
+    rune("SPD", &[], Stat, &[]),
+    rune("POISON", &[], Status, &[]),
+    rune("SLOW", &[], Status, &[]),
+    rune("BURN", &[], Status, &[]),
+    rune("SWORD", &[], Weapon, &[Number]),
+    rune("FIREBALL", &[], Spell, &[Number]),
+];
+
+/// Runes that can be read where `category` is expected.
+pub fn runes(category: RuneCategory) -> impl Iterator<Item = &'static RuneDef> {
+    RUNES.iter().filter(move |rune| category.accepts(rune.category))
+}
+
+/// Finds the rune spelled `word` among the ones `category` accepts.
+pub fn find(category: RuneCategory, word: &str) -> Option<&'static RuneDef> {
+    runes(category).find(|rune| rune.spellings().any(|spelling| spelling == word))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::runes::*;
+
+    #[test]
+    fn test_table() {
+        assert_eq!(find(RuneCategory::Target, "FO-").map(|r| r.keyword), Some("EL"));
+        assert_eq!(find(RuneCategory::Action, "W").map(|r| r.keyword), Some("WAIT"));
+        assert_eq!(find(RuneCategory::Stat, "DEF").map(|r| r.arity()), Some(0));
+        assert_eq!(find(RuneCategory::Action, "DEF").map(|r| r.category), Some(RuneCategory::Action));
+        assert_eq!(find(RuneCategory::Operand, "NOT").map(|r| r.category), Some(RuneCategory::Gate));
+        assert_eq!(find(RuneCategory::Operand, "T>").map(|r| r.keyword), Some("T>="));
+        assert_eq!(find(RuneCategory::Condition, "NOT"), None);
+
+        // A spelling names a single rune within a category.
+        for rune in RUNES {
+            for spelling in rune.spellings() {
+                assert_eq!(find(rune.category, spelling), Some(rune));
+            }
+        }
+    }
+}
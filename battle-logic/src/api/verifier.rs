@@ -0,0 +1,152 @@
+//! Checks a fight_status log by running the fight again.
+
+use std::fmt::{Display, Formatter, Result};
+
+use crate::grammar::fight_status::{outcome_name, read_replay, write_fight, Log, Replay};
+use crate::grammar::fighters_info::{read_fighters, WriteError};
+use crate::grammar::ParseError;
+use crate::logic::prelude::*;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Verdict {
+    Match,
+    /// First event (1-based) where the log differs from the fight.
+    Diverged {
+        event: usize,
+        expected: Entry,
+        found: Entry,
+    },
+}
+
+/// An event of a fight_status log: a turn header, a line of a turn or the outcome.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Entry {
+    Turn(u8),
+    Log(Log),
+    Outcome(State),
+}
+
+/// Error raised when a fight can't be verified.
+#[derive(Debug, PartialEq, Clone)]
+pub enum VerifyError {
+    /// The fighters_info text or the fight_status log doesn't parse.
+    Parse(ParseError),
+    /// The fight can't be logged.
+    Write(WriteError),
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Entry::Turn(number) => write!(f, "- TURN {}", number),
+            Entry::Log(log) => write!(f, "{}", log),
+            Entry::Outcome(state) => write!(f, "= {}", outcome_name(state)),
+        }
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Verdict::Match => write!(f, "the log matches the fight"),
+            Verdict::Diverged { event, expected, found } => {
+                write!(f, "event {}: expected `{}`, found `{}`", event, expected, found)
+            }
+        }
+    }
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            VerifyError::Parse(error) => write!(f, "{}", error),
+            VerifyError::Write(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<ParseError> for VerifyError {
+    fn from(error: ParseError) -> Self {
+        VerifyError::Parse(error)
+    }
+}
+
+impl From<WriteError> for VerifyError {
+    fn from(error: WriteError) -> Self {
+        VerifyError::Write(error)
+    }
+}
+
+fn entries(replay: &Replay) -> impl Iterator<Item = Entry> + '_ {
+    replay.turns.iter()
+        .flat_map(|turn| std::iter::once(Entry::Turn(turn.number)).chain(turn.logs.iter().cloned().map(Entry::Log)))
+        .chain(std::iter::once(Entry::Outcome(replay.outcome)))
+}
+
+/// Compares two replays event by event. Both end with their outcome, so one can't run out of events first.
+pub fn compare(expected: &Replay, found: &Replay) -> Verdict {
+    let diverged = entries(expected).zip(entries(found)).enumerate().find(|(_, (e, f))| e != f);
+    match diverged {
+        Some((i, (expected, found))) => Verdict::Diverged { event: i + 1, expected, found },
+        None => Verdict::Match,
+    }
+}
+
+/// Runs `fight` and compares its events with those of `replay`.
+pub fn verify_fight(fight: &mut Fight, replay: &Replay) -> std::result::Result<Verdict, VerifyError> {
+    let simulated = read_replay(&write_fight(fight)?)?;
+    Ok(compare(&simulated, replay))
+}
+
+/// Runs the fight described by a fighters_info text and compares it with a fight_status log.
+pub fn verify(fighters_info: &str, fight_status: &str) -> std::result::Result<Verdict, VerifyError> {
+    let replay = read_replay(fight_status)?;
+    let (allies, enemies) = read_fighters(fighters_info)?;
+    verify_fight(&mut Fight::build_fight(allies, enemies), &replay)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::verifier::*;
+    use crate::grammar::fight_status::LoggedAction;
+    use crate::simulate;
+
+    #[test]
+    fn test_verify() {
+        let info = include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap();
+        let status = simulate(info).unwrap();
+        assert_eq!(verify(info, &status), Ok(Verdict::Match));
+        assert_eq!(verify(info, &format!("\n{}\n\n", status.replace('\n', "  \n"))), Ok(Verdict::Match));
+        assert_eq!(verify(info, &status.replace("! [A]", "!  [A]")), Ok(Verdict::Match));
+
+        let tampered = status.replacen("= LOST", "= WON", 1);
+        let verdict = verify(info, &tampered).unwrap();
+        assert_eq!(verdict, Verdict::Diverged {
+            event: 5,
+            expected: Entry::Outcome(State::EnemiesVictory),
+            found: Entry::Outcome(State::AlliesVictory),
+        });
+        assert_eq!(verdict.to_string(), "event 5: expected `= LOST`, found `= WON`");
+
+        let missing = status.replacen("! [A] 1 -> DEF\n", "", 1);
+        assert_eq!(verify(info, &missing), Ok(Verdict::Diverged {
+            event: 2,
+            expected: Entry::Log(Log::Action { fighter: 'A', rule: 1, action: LoggedAction::Defense }),
+            found: Entry::Log(Log::Action { fighter: 'B', rule: 0, action: LoggedAction::Attack('A') }),
+        }));
+
+        let longer = status.replacen("= LOST", "- TURN 2\n! [B] 0 -> WAIT\n= LOST", 1);
+        assert_eq!(verify(info, &longer), Ok(Verdict::Diverged {
+            event: 5,
+            expected: Entry::Outcome(State::EnemiesVictory),
+            found: Entry::Turn(2),
+        }));
+
+        let truncated = status.lines().take(3).collect::<Vec<&str>>().join("\n");
+        assert!(matches!(verify(info, &truncated), Err(VerifyError::Parse(_))));
+        assert_eq!(verify(info, "hello").unwrap_err().to_string(), "1:1: expected header");
+        assert!(matches!(verify("[A] HERO\n", &status), Err(VerifyError::Parse(_))));
+    }
+}
@@ -0,0 +1,350 @@
+//! C API of the engine, declared in `include/battle_logic.h`.
+//!
+//! A fight is created from a fighters_info text, then stepped or run. The events it produced are kept
+//! in the fight until cleared, and can be read one by one as [BlEvent]s or as fight_status text.
+//! Strings returned by the API are owned by the caller and freed with [bl_string_free].
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::grammar::fight_status::StatusWriter;
+use crate::grammar::fighters_info::read_fighters;
+use crate::logic::prelude::*;
+
+/// A fight and the events it produced.
+pub struct BlFight {
+    fight: Fight,
+    events: Vec<FightEvent>,
+    /// Set when playing the fight panicked, leaving it in an unknown state.
+    failed: bool,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum BlOutcome {
+    Ongoing,
+    AlliesVictory,
+    EnemiesVictory,
+    Draw,
+    /// The engine failed while playing the fight, which can't go on.
+    Error,
+}
+
+impl From<Option<State>> for BlOutcome {
+    fn from(state: Option<State>) -> Self {
+        match state {
+            None => BlOutcome::Ongoing,
+            Some(State::AlliesVictory) => BlOutcome::AlliesVictory,
+            Some(State::EnemiesVictory) => BlOutcome::EnemiesVictory,
+            Some(State::Draw) => BlOutcome::Draw,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum BlEventKind {
+    TurnStarted,
+    RuleFired,
+    ActionChosen,
+    Reacted,
+    ActionFailed,
+    StatChanged,
+    StatusApplied,
+    StatusTicked,
+    StatusExpired,
+    Died,
+    Outcome,
+}
+
+/// A [FightEvent] flattened for C. Fields that don't apply to the kind are zero.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct BlEvent {
+    pub kind: BlEventKind,
+    /// Letter of the fighter, as in fight_status logs.
+    pub fighter: c_char,
+    /// Letter of the target of an action.
+    pub target: c_char,
+    /// Turn, rule index, relic index, status duration or remaining turns, or [BlOutcome].
+    pub number: u32,
+    /// Stat before a change.
+    pub before: u16,
+    /// Stat after a change, or status potency.
+    pub after: u16,
+    /// Rune of the action, stat or status, e.g. `ATK`, `HP` or `POISON`; empty if none.
+    pub rune: *const c_char,
+}
+
+impl BlEvent {
+    fn new(event: &FightEvent, letters: &StatusWriter) -> Self {
+        let letter = |id: &FighterID| letters.letter(id).map_or(0, |letter| letter as c_char);
+        let mut bl_event = BlEvent { kind: BlEventKind::Outcome, fighter: 0, target: 0, number: 0, before: 0, after: 0, rune: c"".as_ptr() };
+        match event {
+            FightEvent::TurnStarted { turn } => {
+                bl_event.kind = BlEventKind::TurnStarted;
+                bl_event.number = *turn as u32;
+            }
+            FightEvent::RuleFired { fighter, rule } => {
+                bl_event.kind = BlEventKind::RuleFired;
+                bl_event.fighter = letter(fighter);
+                bl_event.number = *rule as u32;
+            }
+            FightEvent::ActionChosen { fighter, action, target } => {
+                bl_event.kind = BlEventKind::ActionChosen;
+                bl_event.fighter = letter(fighter);
+                bl_event.target = letter(target);
+                bl_event.rune = action_rune(action).as_ptr();
+            }
+            FightEvent::Reacted { fighter, relic, action, target } => {
+                bl_event.kind = BlEventKind::Reacted;
+                bl_event.fighter = letter(fighter);
+                bl_event.target = letter(target);
+                bl_event.number = *relic as u32;
+                bl_event.rune = action_rune(action).as_ptr();
+            }
+            FightEvent::ActionFailed { fighter, .. } => {
+                bl_event.kind = BlEventKind::ActionFailed;
+                bl_event.fighter = letter(fighter);
+            }
+            FightEvent::StatChanged { fighter, stat, before, after } => {
+                bl_event.kind = BlEventKind::StatChanged;
+                bl_event.fighter = letter(fighter);
+                bl_event.before = *before;
+                bl_event.after = *after;
+                bl_event.rune = stat_rune(stat).as_ptr();
+            }
+            FightEvent::StatusApplied { fighter, status, duration, potency } => {
+                bl_event.kind = BlEventKind::StatusApplied;
+                bl_event.fighter = letter(fighter);
+                bl_event.number = *duration as u32;
+                bl_event.after = *potency;
+                bl_event.rune = status_rune(status).as_ptr();
+            }
+            FightEvent::StatusTicked { fighter, status, remaining } => {
+                bl_event.kind = BlEventKind::StatusTicked;
+                bl_event.fighter = letter(fighter);
+                bl_event.number = *remaining as u32;
+                bl_event.rune = status_rune(status).as_ptr();
+            }
+            FightEvent::StatusExpired { fighter, status } => {
+                bl_event.kind = BlEventKind::StatusExpired;
+                bl_event.fighter = letter(fighter);
+                bl_event.rune = status_rune(status).as_ptr();
+            }
+            FightEvent::Died { fighter } => {
+                bl_event.kind = BlEventKind::Died;
+                bl_event.fighter = letter(fighter);
+            }
+            FightEvent::Outcome { state } => {
+                bl_event.kind = BlEventKind::Outcome;
+                bl_event.number = BlOutcome::from(Some(*state)) as u32;
+            }
+        }
+        bl_event
+    }
+}
+
+fn action_rune(action: &Action) -> &'static CStr {
+    match action {
+        Action::Attack(..) => c"ATK",
+        Action::Spell(..) => c"SPL",
+        Action::Defense => c"DEF",
+        Action::Wait => c"WAIT",
+    }
+}
+
+fn stat_rune(stat: &Stat) -> &'static CStr {
+    match stat {
+        Stat::Health => c"HP",
+        Stat::Attack => c"ATK",
+        Stat::Defense => c"DEF",
+        Stat::Nature => c"NAT",
+        Stat::Demon => c"DEM",
+        Stat::Speed => c"SPD",
+    }
+}
+
+fn status_rune(status: &Status) -> &'static CStr {
+    match status {
+        Status::Poisoned => c"POISON",
+        Status::Slow => c"SLOW",
+        Status::Burn => c"BURN",
+    }
+}
+
+fn into_c_string(text: String) -> *mut c_char {
+    // Neither fighters_info nor fight_status text can hold a NUL byte.
+    CString::new(text.replace('\0', "")).unwrap().into_raw()
+}
+
+/// Runs `f`, returning `fallback` if it panics: a panic can't unwind into C.
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// Creates a fight from a fighters_info text. On error, including a failure of the engine, returns null
+/// and, if `error` isn't null, stores the message there.
+///
+/// # Safety
+///
+/// `fighters_info` must be a NUL-terminated string, and `error` null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_fight_new(fighters_info: *const c_char, error: *mut *mut c_char) -> *mut BlFight {
+    let result = if fighters_info.is_null() {
+        Err("no fighters_info".to_string())
+    } else {
+        match unsafe { CStr::from_ptr(fighters_info) }.to_str() {
+            Ok(fighters_info) => guard(Err("internal error".to_string()), || {
+                let (allies, enemies) = read_fighters(fighters_info).map_err(|e| e.to_string())?;
+                Ok(Fight::build_fight(allies, enemies))
+            }),
+            Err(e) => Err(e.to_string()),
+        }
+    };
+    match result {
+        Ok(fight) => Box::into_raw(Box::new(BlFight { fight, events: Vec::new(), failed: false })),
+        Err(message) => {
+            if !error.is_null() {
+                unsafe { *error = into_c_string(message) };
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `fight` must come from [bl_fight_new] and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_fight_free(fight: *mut BlFight) {
+    if !fight.is_null() {
+        drop(unsafe { Box::from_raw(fight) });
+    }
+}
+
+impl BlFight {
+    /// Plays with `play`, unless an earlier play failed.
+    fn play(&mut self, play: impl FnOnce(&mut Fight, &mut Vec<FightEvent>) -> BlOutcome) -> BlOutcome {
+        if self.failed {
+            return BlOutcome::Error;
+        }
+        let outcome = guard(BlOutcome::Error, || play(&mut self.fight, &mut self.events));
+        self.failed = outcome == BlOutcome::Error;
+        outcome
+    }
+}
+
+/// Plays the action of the next fighter. Returns [BlOutcome::Error] if `fight` is null or the engine failed.
+///
+/// # Safety
+///
+/// `fight` must come from [bl_fight_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_fight_step(fight: *mut BlFight) -> BlOutcome {
+    let Some(fight) = (unsafe { fight.as_mut() }) else { return BlOutcome::Error };
+    fight.play(|fight, events| fight.step(events).into())
+}
+
+/// Plays until the end of the fight. Returns [BlOutcome::Error] if `fight` is null or the engine failed.
+///
+/// # Safety
+///
+/// `fight` must come from [bl_fight_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_fight_run(fight: *mut BlFight) -> BlOutcome {
+    let Some(fight) = (unsafe { fight.as_mut() }) else { return BlOutcome::Error };
+    fight.play(|fight, events| Some(fight.run(events)).into())
+}
+
+/// Number of events kept since the last [bl_fight_clear_events].
+///
+/// # Safety
+///
+/// `fight` must come from [bl_fight_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_fight_event_count(fight: *const BlFight) -> usize {
+    unsafe { fight.as_ref() }.map_or(0, |fight| fight.events.len())
+}
+
+/// Reads the event at `index` into `event`, returns false if there is none or the engine failed.
+///
+/// # Safety
+///
+/// `fight` must come from [bl_fight_new], and `event` be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_fight_event(fight: *const BlFight, index: usize, event: *mut BlEvent) -> bool {
+    let Some(fight) = (unsafe { fight.as_ref() }) else { return false };
+    match fight.events.get(index) {
+        Some(e) if !event.is_null() => match guard(None, || Some(BlEvent::new(e, &StatusWriter::new(&fight.fight).ok()?))) {
+            Some(e) => {
+                unsafe { *event = e };
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Kept events in the fight_status format, to free with [bl_string_free]. Null if the engine failed.
+///
+/// # Safety
+///
+/// `fight` must come from [bl_fight_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_fight_events_text(fight: *const BlFight) -> *mut c_char {
+    let Some(fight) = (unsafe { fight.as_ref() }) else { return ptr::null_mut() };
+    guard(ptr::null_mut(), || {
+        let Ok(mut writer) = StatusWriter::new(&fight.fight) else { return ptr::null_mut() };
+        fight.events.iter().for_each(|event| writer.push(event.clone()));
+        into_c_string(writer.into_string())
+    })
+}
+
+/// Forgets the kept events.
+///
+/// # Safety
+///
+/// `fight` must come from [bl_fight_new].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_fight_clear_events(fight: *mut BlFight) {
+    if let Some(fight) = unsafe { fight.as_mut() } {
+        fight.events.clear();
+    }
+}
+
+/// # Safety
+///
+/// `string` must come from this API and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bl_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use crate::ffi::*;
+
+    #[test]
+    fn test_failure() {
+        let info = include_str!("../tests/pest/info1.txt").split("=======").nth(1).unwrap();
+        let info = CString::new(info).unwrap();
+        unsafe {
+            let fight = bl_fight_new(info.as_ptr(), ptr::null_mut());
+            assert_eq!(bl_fight_step(fight), BlOutcome::Ongoing);
+            assert_eq!((*fight).play(|_, _| panic!("engine failure")), BlOutcome::Error);
+            assert_eq!(bl_fight_step(fight), BlOutcome::Error);
+            assert_eq!(bl_fight_run(fight), BlOutcome::Error);
+            assert!(bl_fight_event_count(fight) > 0);
+            bl_fight_free(fight);
+            assert_eq!(bl_fight_step(ptr::null_mut()), BlOutcome::Error);
+            assert_eq!(bl_fight_run(ptr::null_mut()), BlOutcome::Error);
+        }
+    }
+}
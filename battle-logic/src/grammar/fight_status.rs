@@ -1,9 +1,297 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use pest::iterators::Pair;
+use pest::Parser;
 use pest_derive::Parser;
 
+use crate::grammar::fighters_info::{WriteError, MAX_FIGHTERS};
+use crate::grammar::ParseError;
+use crate::logic::prelude::*;
+
 #[derive(Parser)]
 #[grammar = "fight_status.pest"]
 struct StatusParser;
 
+/// A fight read back from its fight_status log.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Replay {
+    pub turns: Vec<ReplayTurn>,
+    pub outcome: State,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReplayTurn {
+    pub number: u8,
+    pub logs: Vec<Log>,
+}
+
+/// One line of a turn. Fighters are identified by their letter.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Log {
+    Action {
+        fighter: char,
+        rule: usize,
+        action: LoggedAction,
+    },
+    Reaction {
+        fighter: char,
+        relic: usize,
+        action: LoggedAction,
+    },
+    Status {
+        fighter: char,
+        stat: Stat,
+        before: u16,
+        after: u16,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LoggedAction {
+    Attack(char),
+    Defense,
+    Spell(char),
+    Wait,
+}
+
+/// Renders fight events in the fight_status format.
+///
+/// Fighters get a letter in [FighterID] order: allies first, then enemies.
+pub struct StatusWriter {
+    allies: usize,
+    enemies: usize,
+    rule: usize,
+    output: String,
+}
+
+impl StatusWriter {
+    /// Fails when the fight has more fighters than there are letters.
+    pub fn new(fight: &Fight) -> Result<Self, WriteError> {
+        if fight.fighters.len() > MAX_FIGHTERS {
+            return Err(WriteError::TooManyFighters { count: fight.fighters.len() });
+        }
+        Ok(StatusWriter {
+            allies: fight.fighters.iter().filter(|(id, _)| id.is_ally()).count(),
+            enemies: fight.fighters.iter().filter(|(id, _)| !id.is_ally()).count(),
+            rule: 0,
+            output: String::new(),
+        })
+    }
+
+    /// Letter of a fighter, `None` for [FighterID::None] and fighters that aren't in the fight.
+    pub fn letter(&self, id: &FighterID) -> Option<char> {
+        let index = match id {
+            FighterID::Ally(i) if *i < self.allies => *i,
+            FighterID::Enemy(i) if *i < self.enemies => self.allies + *i,
+            _ => return None,
+        };
+        (b'A'..=b'Z').nth(index).map(char::from)
+    }
+
+    fn action(&self, action: &Action, target: &FighterID) -> Option<LoggedAction> {
+        Some(match action {
+            Action::Attack(_, _) => LoggedAction::Attack(self.letter(target)?),
+            Action::Spell(..) => LoggedAction::Spell(self.letter(target)?),
+            Action::Defense => LoggedAction::Defense,
+            Action::Wait => LoggedAction::Wait,
+        })
+    }
+
+    /// Line of an event, if it has one. An action without a target fails without effect and isn't logged.
+    fn line(&self, event: FightEvent) -> Option<String> {
+        Some(match event {
+            FightEvent::TurnStarted { turn } => format!("- TURN {}", turn),
+            FightEvent::ActionChosen { fighter, action, target } => Log::Action {
+                fighter: self.letter(&fighter)?,
+                rule: self.rule,
+                action: self.action(&action, &target)?,
+            }.to_string(),
+            FightEvent::Reacted { fighter, relic, action, target } => Log::Reaction {
+                fighter: self.letter(&fighter)?,
+                relic,
+                action: self.action(&action, &target)?,
+            }.to_string(),
+            FightEvent::StatChanged { fighter, stat, before, after } => Log::Status {
+                fighter: self.letter(&fighter)?,
+                stat,
+                before,
+                after,
+            }.to_string(),
+            FightEvent::RuleFired { .. }
+            | FightEvent::StatusApplied { .. }
+            | FightEvent::StatusTicked { .. }
+            | FightEvent::StatusExpired { .. }
+            | FightEvent::ActionFailed { .. }
+            | FightEvent::Died { .. } => return None,
+            FightEvent::Outcome { state } => format!("= {}", outcome_name(&state)),
+        })
+    }
+
+    pub fn into_string(self) -> String {
+        self.output
+    }
+}
+
+impl EventSink for StatusWriter {
+    fn push(&mut self, event: FightEvent) {
+        if let FightEvent::RuleFired { rule, .. } = event {
+            self.rule = rule;
+        }
+        if let Some(line) = self.line(event) {
+            self.output.push_str(&line);
+            self.output.push('\n');
+        }
+    }
+}
+
+/// Runs `fight` to the end and returns its fight_status log.
+pub fn write_fight(fight: &mut Fight) -> Result<String, WriteError> {
+    let mut writer = StatusWriter::new(fight)?;
+    fight.run(&mut writer);
+    Ok(writer.into_string())
+}
+
+pub(crate) fn outcome_name(state: &State) -> &'static str {
+    match state {
+        State::AlliesVictory => "WON",
+        State::EnemiesVictory => "LOST",
+        State::Draw => "DRAW",
+    }
+}
+
+impl Display for LoggedAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoggedAction::Attack(target) => write!(f, "ATK [{}]", target),
+            LoggedAction::Defense => write!(f, "DEF"),
+            LoggedAction::Spell(target) => write!(f, "SPL [{}]", target),
+            LoggedAction::Wait => write!(f, "WAIT"),
+        }
+    }
+}
+
+impl Display for Log {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Log::Action { fighter, rule, action } => write!(f, "! [{}] {} -> {}", fighter, rule, action),
+            Log::Reaction { fighter, relic, action } => write!(f, "> [{}] RELIC {} -> {}", fighter, relic, action),
+            Log::Status { fighter, stat, before, after } => {
+                write!(f, ": [{}] {} {} -> {}", fighter, stat, before, after)
+            }
+        }
+    }
+}
+
+impl Display for Replay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for turn in &self.turns {
+            writeln!(f, "- TURN {}", turn.number)?;
+            for log in &turn.logs {
+                writeln!(f, "{}", log)?;
+            }
+        }
+        writeln!(f, "= {}", outcome_name(&self.outcome))
+    }
+}
+
+/// Reads a fight_status log.
+pub fn read_replay(input: &str) -> Result<Replay, ParseError> {
+    let fight_status = StatusParser::parse(Rule::fight_status, input)?.next().unwrap();
+
+    let mut turns = Vec::new();
+    let mut outcome = State::Draw;
+    for pair in fight_status.into_inner() {
+        match pair.as_rule() {
+            Rule::turn => turns.push(read_turn(pair)?),
+            Rule::outcome => outcome = read_outcome(pair),
+            _ => {}
+        }
+    }
+
+    Ok(Replay { turns, outcome })
+}
+
+fn read_turn(turn: Pair<Rule>) -> Result<ReplayTurn, ParseError> {
+    let mut pairs = turn.into_inner();
+    let header = pairs.next().unwrap();
+    let number = read_value(header.into_inner().next().unwrap())?;
+
+    let mut logs = Vec::new();
+    for log in pairs {
+        let log = log.into_inner().next().unwrap();
+        logs.push(match log.as_rule() {
+            Rule::action_log => {
+                let mut inner = log.into_inner();
+                Log::Action {
+                    fighter: read_character(inner.next().unwrap()),
+                    rule: read_value(inner.next().unwrap())?,
+                    action: read_action(inner.next().unwrap()),
+                }
+            }
+            Rule::reaction_log => {
+                let mut inner = log.into_inner();
+                let fighter = read_character(inner.next().unwrap());
+                let origin = inner.next().unwrap();
+                Log::Reaction {
+                    fighter,
+                    relic: read_value(origin.into_inner().next().unwrap())?,
+                    action: read_action(inner.next().unwrap()),
+                }
+            }
+            _ => {
+                let mut inner = log.into_inner();
+                Log::Status {
+                    fighter: read_character(inner.next().unwrap()),
+                    stat: read_stat(inner.next().unwrap()),
+                    before: read_value(inner.next().unwrap())?,
+                    after: read_value(inner.next().unwrap())?,
+                }
+            }
+        });
+    }
+
+    Ok(ReplayTurn { number, logs })
+}
+
+fn read_value<T: FromStr>(pair: Pair<Rule>) -> Result<T, ParseError> {
+    pair.as_str().parse()
+        .map_err(|_| ParseError::at(&pair, format!("number out of range: {}", pair.as_str())))
+}
+
+fn read_character(pair: Pair<Rule>) -> char {
+    pair.as_str().chars().nth(1).unwrap()
+}
+
+fn read_action(pair: Pair<Rule>) -> LoggedAction {
+    let action = pair.into_inner().next().unwrap();
+    match action.as_rule() {
+        Rule::attack => LoggedAction::Attack(read_character(action.into_inner().next().unwrap())),
+        Rule::spell => LoggedAction::Spell(read_character(action.into_inner().next().unwrap())),
+        Rule::defense => LoggedAction::Defense,
+        _ => LoggedAction::Wait,
+    }
+}
+
+fn read_stat(pair: Pair<Rule>) -> Stat {
+    match pair.as_str() {
+        "HP" => Stat::Health,
+        "ATK" => Stat::Attack,
+        "DEF" => Stat::Defense,
+        "NAT" => Stat::Nature,
+        "DEM" => Stat::Demon,
+        _ => Stat::Speed,
+    }
+}
+
+fn read_outcome(pair: Pair<Rule>) -> State {
+    match pair.into_inner().next().unwrap().as_str() {
+        "WON" => State::AlliesVictory,
+        "LOST" => State::EnemiesVictory,
+        _ => State::Draw,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
@@ -11,15 +299,79 @@ mod tests {
     use lazy_static::lazy_static;
     use pest_test::{default_test_dir, PestTester, TestError};
 
-    use crate::grammar::fight_status::{Rule, StatusParser};
+    use pest::Parser;
+
+    use crate::grammar::fight_status::*;
+    use crate::logic::prelude::{Fight, Stat, State};
+    use crate::predefined::prelude::*;
 
     lazy_static! {
         static ref TESTER: PestTester<Rule, StatusParser> =
-          PestTester::new(default_test_dir(), "txt", Rule::fight_status, HashSet::new());
+          PestTester::new(default_test_dir(), "txt", Rule::fight_status, HashSet::from([Rule::EOI]));
     }
 
     #[test]
     fn test_complete_fight() -> Result<(), TestError<Rule>> {
         (*TESTER).evaluate_strict("complete_fight")
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_write_fight() {
+        let mut fight = Fight::build_fight(
+            vec![Fighters::Arches.new(), Fighters::Arches.new()],
+            vec![Fighters::Bat.new()],
+        );
+        let output = write_fight(&mut fight).unwrap();
+
+        assert!(output.starts_with("- TURN 1\n! [B] 1 -> WAIT\n! [A] 1 -> WAIT\n"));
+        assert!(output.contains("! [A] 0 -> ATK [C]\n: [C] DEF 15 -> 0\n"));
+        assert!(output.ends_with("= WON\n"));
+        assert!(StatusParser::parse(Rule::fight_status, &output).is_ok());
+    }
+
+    #[test]
+    fn test_letters() {
+        let bats = |count: usize| (0..count).map(|_| Fighters::Bat.new()).collect::<Vec<Fighter>>();
+        let writer = StatusWriter::new(&Fight::build_fight(bats(25), bats(1))).unwrap();
+        assert_eq!(writer.letter(&FighterID::Ally(24)), Some('Y'));
+        assert_eq!(writer.letter(&FighterID::Enemy(0)), Some('Z'));
+        assert_eq!(writer.letter(&FighterID::Enemy(1)), None);
+        assert_eq!(writer.letter(&FighterID::None), None);
+
+        let mut fight = Fight::build_fight(bats(25), bats(2));
+        assert_eq!(StatusWriter::new(&fight).err(), Some(WriteError::TooManyFighters { count: 27 }));
+        assert_eq!(write_fight(&mut fight), Err(WriteError::TooManyFighters { count: 27 }));
+    }
+
+    #[test]
+    fn test_read_replay() {
+        let replay = read_replay(include_str!("../../tests/pest/complete_fight.txt").split("=======").nth(1).unwrap())
+            .unwrap();
+
+        assert_eq!(replay.turns.len(), 3);
+        assert_eq!(replay.turns[1].logs[0], Log::Action { fighter: 'A', rule: 1, action: LoggedAction::Defense });
+        assert_eq!(replay.turns[2].logs[1], Log::Status { fighter: 'B', stat: Stat::Health, before: 5, after: 0 });
+        assert_eq!(replay.outcome, State::AlliesVictory);
+
+        let replay = read_replay("- TURN 1\n! [A] 0 -> ATK [B]\n> [B] RELIC 2 -> SPL [A]\n= DRAW\n").unwrap();
+        assert_eq!(replay.turns[0].logs[1], Log::Reaction { fighter: 'B', relic: 2, action: LoggedAction::Spell('A') });
+
+        let error = read_replay("- TURN 1\n! [A] 0 -> ATK [B]\n: [B] HP 99999 -> 5\n= WON\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 10));
+        let error = read_replay("- TURN 1\n! [A] 0 -> JUMP\n= WON\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 12));
+        let error = read_replay("- TURN 1\n! [A] 0 -> WAIT\n= WON\ngarbage here\n").unwrap_err();
+        assert_eq!((error.line, error.column), (4, 1));
+    }
+
+    #[test]
+    fn test_replay_round_trip() {
+        let mut fight = Fight::build_fight(
+            vec![Fighters::Arches.new(), Fighters::Arches.new()],
+            vec![Fighters::Bat.new()],
+        );
+        let output = write_fight(&mut fight).unwrap();
+
+        assert_eq!(read_replay(&output).unwrap().to_string(), output);
+    }
+}
@@ -1,13 +1,274 @@
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+
+use pest::error::{Error, ErrorVariant, LineColLocation};
+use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 
+use crate::api::input::read_rule;
+use crate::grammar::ParseError;
+use crate::logic::prelude::{Action, Fighter, Relic, Spell, SpellKind, SpellRef, Stat, Stats, Weapon, WeaponKind, WeaponRef};
+use crate::predefined::prelude::{get_relic, get_spell, get_weapon, Relics};
+
 #[derive(Parser)]
 #[grammar = "fighters_info.pest"]
 struct InfoParser;
 
-fn test_valid(parent_rule: Rule, input: &str) {
-    let result = InfoParser::parse(parent_rule, input);
-    assert!(result.is_ok());
+/// Reads the two teams of a fighters_info description.
+pub fn read_fighters(input: &str) -> Result<(Vec<Fighter>, Vec<Fighter>), ParseError> {
+    let fighters_info = InfoParser::parse(Rule::fighters_info, input)
+        .map_err(|error| explain(input, error))?
+        .next()
+        .unwrap();
+    let mut teams = fighters_info.into_inner().filter(|pair| pair.as_rule() == Rule::team);
+    let (allies, enemies) = (teams.next().unwrap(), teams.next().unwrap());
+
+    if let Some(extra) = allies.clone().into_inner().chain(enemies.clone().into_inner()).nth(MAX_FIGHTERS) {
+        return Err(ParseError::at(&extra, format!("at most {} fighters, one per letter", MAX_FIGHTERS)));
+    }
+    Ok((read_team(allies)?, read_team(enemies)?))
+}
+
+/// Errors inside a rule are explained by the rune reader, which knows what each rune expects
+/// and suggests spellings.
+fn explain(input: &str, error: Error<Rule>) -> ParseError {
+    let line = match error.line_col {
+        LineColLocation::Pos((line, _)) | LineColLocation::Span((line, _), _) => line,
+    };
+    if let ErrorVariant::ParsingError { positives, .. } = &error.variant
+        && positives.iter().any(is_rune)
+        && let Some(text) = input.lines().nth(line - 1)
+        && !text.trim().is_empty()
+        && let Err(rune_error) = read_rule(text)
+    {
+        return ParseError { line, column: rune_error.column(text), message: rune_error.message() };
+    }
+    error.into()
+}
+
+/// Whether the kind of pair is part of a rule of the rune language.
+fn is_rune(kind: &Rule) -> bool {
+    matches!(
+        kind,
+        Rule::gate | Rule::id | Rule::not | Rule::or | Rule::nor | Rule::xor | Rule::nxor | Rule::and | Rule::nand
+            | Rule::operand | Rule::cond | Rule::every_x_turns | Rule::on_turn | Rule::from_turn | Rule::hp_less
+            | Rule::hp_more | Rule::has_status | Rule::status | Rule::action | Rule::atk | Rule::spl | Rule::def
+            | Rule::wait | Rule::target | Rule::slf | Rule::enemy_less | Rule::enemy_more | Rule::ally_less
+            | Rule::ally_more | Rule::stat | Rule::weapon | Rule::spell | Rule::fireball
+    )
+}
+
+fn read_team(team: Pair<Rule>) -> Result<Vec<Fighter>, ParseError> {
+    team.into_inner().map(read_character).collect()
+}
+
+fn read_character(character: Pair<Rule>) -> Result<Fighter, ParseError> {
+    let mut inner = character.into_inner();
+    let _char_id = inner.next().unwrap();
+    let name = inner.next().unwrap().as_str().to_string();
+    let stats = read_stats(inner.next().unwrap())?;
+
+    let mut rules = Vec::new();
+    let mut weapons: Vec<Box<dyn Weapon>> = Vec::new();
+    let mut spell: Option<Box<dyn Spell>> = None;
+    for pair in inner.next().unwrap().into_inner() {
+        // The grammar checked the structure of the rule, the rune reader builds it.
+        let text = pair.as_str().trim_end();
+        let rule = read_rule(text).map_err(|error| {
+            let (line, column) = pair.line_col();
+            ParseError { line, column: column + error.column(text) - 1, message: error.message() }
+        })?;
+        let action = pair.into_inner().nth(1).unwrap().into_inner().next().unwrap();
+        match (&rule.action, action.into_inner().last()) {
+            (Action::Attack(_, weapon), Some(reference)) => add_weapon(&mut weapons, weapon, &reference)?,
+            (Action::Spell(_, cast), Some(reference)) => add_spell(&mut spell, cast, &reference, &name)?,
+            _ => {}
+        }
+        rules.push(rule);
+    }
+
+    let mut relics: Vec<Box<dyn Relic>> = Vec::new();
+    for item in inner.flat_map(|section| section.into_inner()) {
+        match item.as_rule() {
+            Rule::weapon => {
+                let weapon = WeaponRef::new(WeaponKind::Sword, read_value(item.clone().into_inner().next().unwrap())?);
+                add_weapon(&mut weapons, &weapon, &item)?;
+            }
+            Rule::spell => {
+                let fireball = item.clone().into_inner().next().unwrap();
+                let cast = SpellRef::new(SpellKind::Fireball, read_value(fireball.into_inner().next().unwrap())?);
+                add_spell(&mut spell, &cast, &item, &name)?;
+            }
+            _ => relics.push(get_relic(item.as_str()).ok_or_else(|| {
+                ParseError::at(&item, format!("unknown relic: {}", item.as_str()))
+            })?),
+        }
+    }
+
+    let mut fighter = Fighter::new(name, stats, rules, weapons, spell);
+    fighter.set_relics(relics);
+    Ok(fighter)
+}
+
+fn add_weapon(weapons: &mut Vec<Box<dyn Weapon>>, weapon: &WeaponRef, reference: &Pair<Rule>) -> Result<(), ParseError> {
+    if !weapons.iter().any(|w| (w.get_kind(), w.get_level()) == (weapon.kind, weapon.level)) {
+        weapons.push(get_weapon(weapon.kind, weapon.level).ok_or_else(|| {
+            ParseError::at(reference, format!("unknown weapon: {}", weapon))
+        })?);
+    }
+    Ok(())
+}
+
+fn add_spell(
+    spell: &mut Option<Box<dyn Spell>>,
+    cast: &SpellRef,
+    reference: &Pair<Rule>,
+    name: &str,
+) -> Result<(), ParseError> {
+    match spell {
+        Some(s) if (s.get_kind(), s.get_level()) == (cast.kind, cast.level) => {}
+        Some(_) => return Err(ParseError::at(reference, format!("{} can only carry one spell", name))),
+        None => *spell = Some(get_spell(cast.kind, cast.level).ok_or_else(|| {
+            ParseError::at(reference, format!("unknown spell: {}", cast))
+        })?),
+    }
+    Ok(())
+}
+
+fn read_stats(stats: Pair<Rule>) -> Result<Stats, ParseError> {
+    let values = stats
+        .into_inner()
+        .map(|stat| read_value(stat.into_inner().next().unwrap()))
+        .collect::<Result<Vec<u16>, ParseError>>()?;
+    Ok(Stats::new(values[0], values[1], values[2], values[3], values[4], values[5]))
+}
+
+fn read_value<T: FromStr>(pair: Pair<Rule>) -> Result<T, ParseError> {
+    pair.as_str().parse()
+        .map_err(|_| ParseError::at(&pair, format!("number out of range: {}", pair.as_str())))
+}
+
+/// Fighters of both teams that can be written, one per letter.
+pub const MAX_FIGHTERS: usize = 26;
+
+/// Error raised when a team cannot be described in the fighters_info format.
+#[derive(Debug, PartialEq, Clone)]
+pub enum WriteError {
+    InvalidName { fighter: String },
+    MissingWeapon { fighter: String },
+    MissingSpell { fighter: String },
+    /// Only predefined relics have a name in the format.
+    UnknownRelic { fighter: String, relic: String },
+    EmptyTeam,
+    /// Fighters are named by a letter, which limits a fight to 26 of them.
+    TooManyFighters { count: usize },
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::InvalidName { fighter } => write!(f, "{} is not a valid fighter name", fighter),
+            WriteError::MissingWeapon { fighter } => write!(f, "{} attacks without a weapon", fighter),
+            WriteError::MissingSpell { fighter } => write!(f, "{} casts without a spell", fighter),
+            WriteError::UnknownRelic { fighter, relic } => write!(f, "{} carries {}, which isn't a predefined relic", fighter, relic),
+            WriteError::EmptyTeam => write!(f, "both teams need a fighter"),
+            WriteError::TooManyFighters { count } => {
+                write!(f, "{} fighters, at most {} can be written", count, MAX_FIGHTERS)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// Writes the two teams as a fighters_info description.
+///
+/// Fighters get a letter in order: allies first, then enemies.
+pub fn write_fighters(allies: &[Fighter], enemies: &[Fighter]) -> Result<String, WriteError> {
+    if allies.is_empty() || enemies.is_empty() {
+        return Err(WriteError::EmptyTeam);
+    }
+    if allies.len() + enemies.len() > MAX_FIGHTERS {
+        return Err(WriteError::TooManyFighters { count: allies.len() + enemies.len() });
+    }
+
+    let mut output = String::new();
+    let mut letters = (b'A'..=b'Z').map(char::from);
+
+    for (i, team) in [allies, enemies].into_iter().enumerate() {
+        if i > 0 {
+            output.push_str("\n---\n\n");
+        }
+        for (fighter, letter) in team.iter().zip(letters.by_ref()) {
+            write_character(&mut output, letter, fighter)?;
+        }
+    }
+
+    Ok(output)
+}
+
+fn write_character(output: &mut String, letter: char, fighter: &Fighter) -> Result<(), WriteError> {
+    let name = fighter.get_name();
+    if name.is_empty() || !name.chars().all(char::is_alphabetic) {
+        return Err(WriteError::InvalidName { fighter: name.clone() });
+    }
+
+    let stats = fighter.get_base_stats();
+    let stat = |s: &Stat| stats.get(s);
+    writeln!(output, "[{}] {}", letter, name).unwrap();
+    writeln!(output, "    STATS").unwrap();
+    writeln!(output, "        HP {} ATK {} DEF {}", stat(&Stat::Health), stat(&Stat::Attack), stat(&Stat::Defense)).unwrap();
+    writeln!(output, "        NAT {} DEM {} SPD {}", stat(&Stat::Nature), stat(&Stat::Demon), stat(&Stat::Speed)).unwrap();
+    writeln!(output, "    RULES").unwrap();
+
+    for rule in fighter.get_rules() {
+        match &rule.action {
+            Action::Attack(_, reference) if fighter.get_weapon(reference).is_none() => {
+                return Err(WriteError::MissingWeapon { fighter: name.clone() });
+            }
+            Action::Spell(_, cast)
+                if !fighter.get_spell().is_some_and(|s| (s.get_kind(), s.get_level()) == (cast.kind, cast.level)) =>
+            {
+                return Err(WriteError::MissingSpell { fighter: name.clone() });
+            }
+            _ => {}
+        }
+        writeln!(output, "        {}", rule).unwrap();
+    }
+
+    let mut equipment = Vec::new();
+    for weapon in fighter.get_weapons() {
+        let weapon = WeaponRef::new(weapon.get_kind(), weapon.get_level());
+        if !fighter.get_rules().iter().any(|rule| matches!(&rule.action, Action::Attack(_, w) if *w == weapon)) {
+            equipment.push(weapon.to_string());
+        }
+    }
+    if let Some(spell) = fighter.get_spell() {
+        let cast = SpellRef::new(spell.get_kind(), spell.get_level());
+        if !fighter.get_rules().iter().any(|rule| matches!(&rule.action, Action::Spell(_, s) if *s == cast)) {
+            equipment.push(cast.to_string());
+        }
+    }
+    if !equipment.is_empty() {
+        writeln!(output, "    EQUIPMENT").unwrap();
+        writeln!(output, "        {}", equipment.join(" ")).unwrap();
+    }
+
+    let mut relics = Vec::new();
+    for relic in fighter.get_relics() {
+        let relic = relic.get_name().to_uppercase();
+        if Relics::from_name(&relic).is_none() {
+            return Err(WriteError::UnknownRelic { fighter: name.clone(), relic });
+        }
+        relics.push(relic);
+    }
+    if !relics.is_empty() {
+        writeln!(output, "    RELICS").unwrap();
+        writeln!(output, "        {}", relics.join(" ")).unwrap();
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -17,15 +278,270 @@ mod tests {
     use lazy_static::lazy_static;
     use pest_test::{default_test_dir, PestTester, TestError};
 
-    use crate::grammar::fighters_info::{Rule, InfoParser};
+    use crate::api::input::read_rule;
+    use crate::api::runes::{RuneCategory, RuneDef, RUNES};
+    use crate::grammar::fighters_info::{read_fighters, write_fighters, Rule, InfoParser, WriteError};
+    use crate::logic::prelude as logic;
+    use crate::logic::prelude::*;
+    use crate::predefined::prelude::*;
 
     lazy_static! {
         static ref TESTER: PestTester<Rule, InfoParser> =
-          PestTester::new(default_test_dir(), "txt", Rule::fighters_info, HashSet::new());
+          PestTester::new(default_test_dir(), "txt", Rule::fighters_info, HashSet::from([Rule::EOI]));
     }
 
     #[test]
     fn test_complete_fight() -> Result<(), TestError<Rule>> {
         (*TESTER).evaluate_strict("info1")
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_aliases() -> Result<(), TestError<Rule>> {
+        (*TESTER).evaluate_strict("info2")?;
+
+        let (allies, enemies) = read_fighters(include_str!("../../tests/pest/info2.txt").split("=======").nth(1).unwrap())
+            .unwrap();
+        assert_eq!(allies[0].get_rules()[0], read_rule("AND LXHP 30 SELF T>= 2 DEF").unwrap());
+        assert_eq!(allies[0].get_rules()[3].action, Action::Wait);
+        assert_eq!(
+            enemies[0].get_rules()[0].gate,
+            Gate::or(Condition::MoreXHP(5, Target::AllyMost(Stat::Health)), Condition::HasStatus(Target::Them, Status::Poisoned)),
+        );
+
+        // Every spelling of the rune table loads, and as its keyword.
+        let argument = |category: &RuneCategory| match category {
+            RuneCategory::Gate | RuneCategory::Operand => "ID EXT 1",
+            RuneCategory::Condition => "EXT 1",
+            RuneCategory::Target => "SELF",
+            RuneCategory::Stat => "HP",
+            RuneCategory::Status => "POISON",
+            RuneCategory::Weapon => "SWORD 1",
+            RuneCategory::Spell => "FIREBALL 1",
+            RuneCategory::Number => "1",
+            RuneCategory::Action | RuneCategory::End => "DEF",
+        };
+        let load = |rune: &RuneDef, spelling: &'static str| {
+            let phrase = [spelling].into_iter().chain(rune.arguments.iter().map(argument)).collect::<Vec<&str>>().join(" ");
+            let rule = match rune.category {
+                RuneCategory::Gate | RuneCategory::Operand => format!("{} DEF", phrase),
+                RuneCategory::Condition => format!("ID {} DEF", phrase),
+                RuneCategory::Action => format!("ID EXT 1 {}", phrase),
+                RuneCategory::Target => format!("ID LXHP 1 {} DEF", phrase),
+                RuneCategory::Stat => format!("ID LXHP 1 EL {} DEF", phrase),
+                RuneCategory::Status => format!("ID STATUS SELF {} DEF", phrase),
+                RuneCategory::Weapon => format!("ID EXT 1 ATK SELF {}", phrase),
+                RuneCategory::Spell => format!("ID EXT 1 SPL SELF {}", phrase),
+                RuneCategory::Number | RuneCategory::End => unreachable!(),
+            };
+            let info = format!(
+                "[A] HERO\n    STATS\n        HP 10 ATK 5 DEF 0\n        NAT 0 DEM 0 SPD 5\n    RULES\n        {}\n\n---\n\n{}",
+                rule,
+                "[B] ENEMY\n    STATS\n        HP 10 ATK 2 DEF 0\n        NAT 2 DEM 8 SPD 3\n    RULES\n        ID EXT 1 DEF\n",
+            );
+            let (allies, _) = read_fighters(&info).unwrap_or_else(|e| panic!("{}: {}", rule, e));
+            allies[0].get_rules()[0].clone()
+        };
+        for rune in RUNES {
+            for spelling in rune.spellings() {
+                assert_eq!(load(rune, spelling), load(rune, rune.keyword), "{}", spelling);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_fighters() {
+        let (allies, enemies) =
+            read_fighters(include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap()).unwrap();
+
+        assert_eq!(allies.len(), 1);
+        assert_eq!(enemies.len(), 1);
+        let hero = &allies[0];
+        assert_eq!(hero.get_name(), "HERO");
+        assert_eq!(hero.get_stat(&Stat::Health), 10);
+        assert_eq!(hero.get_stat(&Stat::Speed), 5);
+        assert_eq!(hero.get_weapons().len(), 1);
+        assert_eq!(hero.get_weapon(&WeaponRef::new(WeaponKind::Sword, 1)).map(|w| w.get_level()), Some(1));
+        assert_eq!(enemies[0].get_stat(&Stat::Demon), 8);
+        assert_eq!(enemies[0].get_weapons()[0].get_name(), "Iron Sword");
+
+        let error = read_fighters("[A] HERO\n    STATS\n").err().unwrap();
+        assert_eq!(error.line, 3);
+        // Fighters of a team aren't separated by empty lines.
+        let bat = "[A] BAT\n    STATS\n        HP 1 ATK 1 DEF 1\n        NAT 1 DEM 1 SPD 1\n    RULES\n        ID EXT 1 DEF\n";
+        let error = read_fighters(&format!("{}\n{}\n---\n\n{}", bat, bat, bat)).err().unwrap();
+        assert_eq!(error.to_string(), "7:1: expected char_id, equipment, relics, or gate");
+        let info = include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap();
+        let error = read_fighters(&format!("{}\ngarbage here\n", info)).err().unwrap();
+        assert_eq!((error.line, error.column), (info.lines().count() + 2, 1));
+
+        let (allies, _) = read_fighters("\
+[A] HERO
+    STATS
+        HP 10 ATK 5 DEF 0
+        NAT 0 DEM 0 SPD 5
+    RULES
+        ID EXT 2 ATK EL HP SWORD 1
+        ID EXT 1 ATK FO- HP SWORD 2
+
+---
+
+[B] ENEMY
+    STATS
+        HP 10 ATK 2 DEF 0
+        NAT 2 DEM 8 SPD 3
+    RULES
+        ID T= 3 W
+").unwrap();
+        assert_eq!(allies[0].get_weapons().len(), 2);
+        assert_eq!(
+            allies[0].get_rules()[1].action,
+            Action::Attack(Target::FoeLess(Stat::Health), WeaponRef::new(WeaponKind::Sword, 2)),
+        );
+
+        let error = read_fighters(&include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap()
+            .replace("SWORD 2", "SWORD 9")).err().unwrap();
+        assert_eq!(error.message, "unknown weapon: SWORD 9");
+
+        let error = read_fighters(&include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap()
+            .replace("ATK EM HP", "ATK EN HP")).err().unwrap();
+        assert_eq!((error.line, error.column), (18, 22));
+        assert_eq!(error.message, "expected a target, found EN (did you mean EL or EM?)");
+
+        let error = read_fighters(&include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap()
+            .replace("ID EXT 1 ATK", "ID EXT 300 ATK")).err().unwrap();
+        assert_eq!((error.line, error.column), (18, 16));
+        assert_eq!(error.message, "expected a number, found 300");
+    }
+
+    #[test]
+    fn test_write_fighters() {
+        let info = include_str!("../../tests/pest/info1.txt").split("=======").nth(1).unwrap().trim_start();
+        let (allies, enemies) = read_fighters(info).unwrap();
+        assert_eq!(write_fighters(&allies, &enemies).unwrap(), info.trim_end().to_string() + "\n");
+
+        let mut arches = Fighters::Arches.new();
+        arches.set_rules(vec![
+            Rules::Careful.new(),
+            logic::Rule { gate: Gate::nxor(Condition::OnTurn(3), Condition::MoreXHP(50, Target::AllyMost(Stat::Speed))),
+                action: Action::Wait },
+            logic::Rule {
+                gate: Gate::or(
+                    Condition::HasStatus(Target::FoeMost(Stat::Demon), Status::Burn),
+                    Gate::and(
+                        Condition::HasStatus(Target::Them, Status::Poisoned),
+                        Gate::not(Condition::OnTurn(1)),
+                    ),
+                ),
+                action: Action::Defense,
+            },
+            Rules::Attack2.new(),
+        ]);
+        let team = vec![arches, Fighters::Arches.new()];
+        let output = write_fighters(&team, &[Fighters::Bat.new()]).unwrap();
+        let (allies, enemies) = read_fighters(&output).unwrap();
+        assert_eq!(allies.len(), 2);
+        assert_eq!(enemies.len(), 1);
+        for (read, written) in allies.iter().chain(enemies.iter()).zip(team.iter().chain([Fighters::Bat.new()].iter())) {
+            assert_eq!(read.get_name(), written.get_name());
+            assert_eq!(read.get_base_stats(), written.get_base_stats());
+            assert_eq!(read.get_rules(), written.get_rules());
+        }
+        assert_eq!(write_fighters(&allies, &enemies).unwrap(), output);
+
+        let unarmed = Fighter::new("Monk".to_string(), Stats::new(1, 1, 1, 1, 1, 1), vec![Rules::Attack2.new()], vec![], None);
+        assert_eq!(
+            write_fighters(&[unarmed], &[Fighters::Bat.new()]),
+            Err(WriteError::MissingWeapon { fighter: "Monk".to_string() }),
+        );
+
+        // Equipment not used by a rule is written too.
+        let knight = Fighter::new(
+            "Knight".to_string(),
+            Stats::new(20, 3, 5, 1, 1, 2),
+            vec![Rules::Careful.new()],
+            vec![get_weapon(WeaponKind::Sword, 1).unwrap(), get_weapon(WeaponKind::Sword, 2).unwrap()],
+            get_spell(SpellKind::Fireball, 1),
+        );
+        let output = write_fighters(&[knight], &[Fighters::Bat.new()]).unwrap();
+        assert!(output.contains("    EQUIPMENT\n        SWORD 1 SWORD 2 FIREBALL 1\n"));
+        let (allies, enemies) = read_fighters(&output).unwrap();
+        assert_eq!(allies[0].get_weapons().len(), 2);
+        assert_eq!(allies[0].get_spell().map(|s| s.get_level()), Some(1));
+        assert_eq!(write_fighters(&allies, &enemies).unwrap(), output);
+
+        // So are relics, in order.
+        let mut arches = Fighters::Arches.new();
+        arches.set_relics(vec![Box::new(Relics::Thorns.new()), Box::new(Relics::Aegis.new())]);
+        let output = write_fighters(&[arches], &[Fighters::Bat.new()]).unwrap();
+        assert!(output.contains("    RELICS\n        THORNS AEGIS\n"));
+        let (allies, enemies) = read_fighters(&output).unwrap();
+        assert_eq!(allies[0].get_relics().iter().map(|r| r.get_name()).collect::<Vec<String>>(), ["Thorns", "Aegis"]);
+        assert_eq!(write_fighters(&allies, &enemies).unwrap(), output);
+        let error = read_fighters(&output.replace("AEGIS", "SHIELD")).err().unwrap();
+        assert_eq!((error.line, error.column, error.message.as_str()), (8, 16, "unknown relic: SHIELD"));
+
+        struct Mirror;
+        impl Relic for Mirror {
+            fn get_name(&self) -> String {
+                "Mirror".to_string()
+            }
+
+            fn react(&self, _: &Trigger) -> Option<Action> {
+                None
+            }
+        }
+        let mut bat = Fighters::Bat.new();
+        bat.set_relics(vec![Box::new(Mirror)]);
+        assert_eq!(
+            write_fighters(&[Fighters::Bat.new()], &[bat]),
+            Err(WriteError::UnknownRelic { fighter: "Bat".to_string(), relic: "MIRROR".to_string() }),
+        );
+
+        assert_eq!(write_fighters(&[], &[]), Err(WriteError::EmptyTeam));
+        assert_eq!(write_fighters(&[Fighters::Bat.new()], &[]), Err(WriteError::EmptyTeam));
+        let bats = (0..25).map(|_| Fighters::Bat.new()).collect::<Vec<Fighter>>();
+        let output = write_fighters(&bats, &[Fighters::Bat.new()]).unwrap();
+        assert!(output.contains("[Y] Bat") && output.contains("[Z] Bat"));
+        assert_eq!(read_fighters(&output).unwrap().0.len(), 25);
+        assert_eq!(
+            write_fighters(&bats, &[Fighters::Bat.new(), Fighters::Bat.new()]),
+            Err(WriteError::TooManyFighters { count: 27 }),
+        );
+        let error = read_fighters(&output.replace("\n---", "[Z] Bat\n    STATS\n        HP 1 ATK 1 DEF 1\n        NAT 1 DEM 1 SPD 1\n    RULES\n\n---"))
+            .err().unwrap();
+        assert_eq!(error.message, "at most 26 fighters, one per letter");
+    }
+
+    #[test]
+    fn test_spells() {
+        let info = "\
+[A] MAGE
+    STATS
+        HP 30 ATK 2 DEF 0
+        NAT 0 DEM 6 SPD 5
+    RULES
+        ID EXT 3 SPL EM DEM FIREBALL 2
+        ID EXT 1 ATK EL HP SWORD 1
+
+---
+
+[B] ENEMY
+    STATS
+        HP 10 ATK 2 DEF 0
+        NAT 2 DEM 8 SPD 3
+    RULES
+        ID EXT 1 SPL SELF FIREBALL 3
+";
+        let error = read_fighters(info).err().unwrap();
+        assert_eq!((error.line, error.message.as_str()), (16, "unknown spell: FIREBALL 3"));
+
+        let info = info.replace("FIREBALL 3", "FIREBALL 1");
+        let (allies, enemies) = read_fighters(&info).unwrap();
+        let mage = &allies[0];
+        assert_eq!(mage.get_spell().map(|s| s.get_name()), Some("Great Fireball".to_string()));
+        assert_eq!(mage.get_rules()[0].action,
+                   Action::Spell(Target::FoeMost(Stat::Demon), SpellRef::new(SpellKind::Fireball, 2)));
+        assert_eq!(write_fighters(&allies, &enemies).unwrap(), info);
+    }
+}
@@ -1,2 +1,41 @@
-mod fight_status;
-mod fighters_info;
\ No newline at end of file
+use std::fmt::{Display, Formatter};
+
+use pest::error::{Error, LineColLocation};
+use pest::iterators::Pair;
+use pest::RuleType;
+
+pub mod fight_status;
+pub mod fighters_info;
+
+/// Error raised while reading a text format, located in the input.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn at<R: RuleType>(pair: &Pair<R>, message: String) -> Self {
+        let (line, column) = pair.line_col();
+        ParseError { line, column, message }
+    }
+}
+
+impl<R: RuleType> From<Error<R>> for ParseError {
+    fn from(error: Error<R>) -> Self {
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(pos) => pos,
+            LineColLocation::Span(start, _) => start,
+        };
+        ParseError { line, column, message: error.variant.message().to_string() }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,53 @@
+//! Deterministic fights between two teams of fighters.
+//!
+//! Teams are described in the fighters_info format and fights are logged in the fight_status format,
+//! see the readme. [simulate] goes from one to the other in a single call.
+//! The crate is also built as a C library, see [ffi].
+
+pub mod api;
+pub mod ffi;
+pub mod grammar;
+pub mod logic;
+pub mod predefined;
+
+use crate::grammar::fight_status::write_fight;
+use crate::grammar::fighters_info::read_fighters;
+use crate::grammar::ParseError;
+use crate::logic::prelude::Fight;
+
+/// Runs the fight described by a fighters_info text and returns its fight_status log.
+pub fn simulate(fighters_info: &str) -> Result<String, ParseError> {
+    let (allies, enemies) = read_fighters(fighters_info)?;
+    let mut fight = Fight::build_fight(allies, enemies);
+    // read_fighters reads at most MAX_FIGHTERS fighters, which all have a letter.
+    Ok(write_fight(&mut fight).expect("too many fighters"))
+}
+
+/// Everything needed to describe teams, run fights and read their logs.
+pub mod prelude {
+    pub use crate::api::input::{read_rule, read_rules, RuneError};
+    pub use crate::api::verifier::{verify, Verdict, VerifyError};
+    pub use crate::grammar::fight_status::{read_replay, write_fight, Log, LoggedAction, Replay, StatusWriter};
+    pub use crate::grammar::fighters_info::{read_fighters, write_fighters, WriteError};
+    pub use crate::grammar::ParseError;
+    pub use crate::logic::prelude::*;
+    pub use crate::predefined::prelude::*;
+    pub use crate::simulate;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grammar::fight_status::read_replay;
+    use crate::simulate;
+
+    #[test]
+    fn test_simulate() {
+        let info = include_str!("../tests/pest/info1.txt").split("=======").nth(1).unwrap();
+        let status = simulate(info).unwrap();
+        assert!(status.starts_with("- TURN 1\n! [A] 1 -> DEF\n"));
+        assert_eq!(read_replay(&status).unwrap().to_string(), status);
+        assert_eq!(simulate(info).unwrap(), status);
+
+        assert_eq!(simulate("[A] HERO\n").err().unwrap().line, 2);
+    }
+}
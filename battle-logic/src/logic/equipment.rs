@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use crate::logic::prelude::*;
 
-#[derive(Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Element {
     Neutral,
     Demonic,
@@ -13,34 +15,75 @@ pub enum WeaponTarget {
     Other,
 }
 
-/// TODO: Make [Weapon] a rune in [crate::logic::rule]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum WeaponKind {
+    Sword,
+}
+
+/// Weapons are referenced from rules with a [WeaponRef].
 pub trait Weapon {
     fn get_name(&self) -> String;
+    fn get_kind(&self) -> WeaponKind;
+    fn get_level(&self) -> u8;
     fn use_weapon(&self, user: &Fighter, target: &Fighter) -> Vec<(WeaponTarget, Consequence)>;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpellKind {
+    Fireball,
+}
+
+pub trait Spell {
+    fn get_name(&self) -> String;
+    fn get_kind(&self) -> SpellKind;
+    fn get_level(&self) -> u8;
+    fn cast(&self, user: &Fighter, target: &Fighter) -> Vec<(WeaponTarget, Consequence)>;
+}
+
+/// Fight event a relic can react to, seen from the fighter holding it.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Trigger {
+    TurnStarted,
+    TookDamage {
+        amount: u16,
+    },
+    AllyDied,
+}
+
+pub trait Relic {
+    fn get_name(&self) -> String;
+    fn react(&self, trigger: &Trigger) -> Option<Action>;
+}
+
 #[derive(Clone)]
 pub enum Consequence {
     Attack {
         damage: u16,
+        element: Element,
     },
     Buff {
         stat: Stat,
         amount: i32,
         duration: u8,
     },
+    Inflict {
+        status: Status,
+        duration: u8,
+        potency: u16,
+    },
 }
 
 impl Consequence {
     pub fn apply_on(&self, fighter: &mut Fighter) {
         match self {
-            Consequence::Attack { damage } => fighter.damage(*damage),
-            Consequence::Buff { .. } => {}
+            Consequence::Attack { damage, element } => fighter.damage(*damage, element),
+            Consequence::Buff { stat, amount, duration } => fighter.buff(stat.clone(), *amount, *duration),
+            Consequence::Inflict { status, duration, potency } => fighter.inflict(*status, *duration, *potency),
         }
     }
 
     pub fn from_damage(element: &Element, damage: u16, user: &Fighter) -> Consequence {
         let attack = user.calc_attack(element);
-        Consequence::Attack { damage: attack + damage }
+        Consequence::Attack { damage: attack + damage, element: element.clone() }
     }
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use crate::logic::prelude::*;
+
+/// Something that happened during a [Fight], in the order it happened.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum FightEvent {
+    TurnStarted {
+        turn: u8,
+    },
+    /// `rule` is the index of the rule in the fighter's rule list.
+    /// The default rule comes after all of them.
+    RuleFired {
+        fighter: FighterID,
+        rule: usize,
+    },
+    ActionChosen {
+        fighter: FighterID,
+        action: Action,
+        target: FighterID,
+    },
+    /// `relic` is the index of the relic in the fighter's relic list.
+    Reacted {
+        fighter: FighterID,
+        relic: usize,
+        action: Action,
+        target: FighterID,
+    },
+    ActionFailed {
+        fighter: FighterID,
+        error: ActionError,
+    },
+    StatChanged {
+        fighter: FighterID,
+        stat: Stat,
+        before: u16,
+        after: u16,
+    },
+    StatusApplied {
+        fighter: FighterID,
+        status: Status,
+        duration: u8,
+        potency: u16,
+    },
+    /// An active status took effect at the start of the fighter's turn.
+    StatusTicked {
+        fighter: FighterID,
+        status: Status,
+        remaining: u8,
+    },
+    StatusExpired {
+        fighter: FighterID,
+        status: Status,
+    },
+    Died {
+        fighter: FighterID,
+    },
+    Outcome {
+        state: State,
+    },
+}
+
+/// Consumer of the events produced by a [Fight].
+pub trait EventSink {
+    fn push(&mut self, event: FightEvent);
+}
+
+impl EventSink for Vec<FightEvent> {
+    fn push(&mut self, event: FightEvent) {
+        Vec::push(self, event);
+    }
+}
+
+/// Narrates a fight on the console.
+pub struct Narrator {
+    fighters: Vec<(FighterID, String, u16)>,
+}
+
+impl Narrator {
+    pub fn new(fight: &Fight) -> Self {
+        Narrator {
+            fighters: fight.fighters
+                .iter()
+                .map(|(id, f)| {
+                    let f = f.borrow();
+                    (*id, f.get_name().clone(), f.get_stat(&Stat::Health))
+                })
+                .collect(),
+        }
+    }
+
+    fn name(&self, id: &FighterID) -> &str {
+        match self.fighters.iter().find(|(f_id, _, _)| f_id == id) {
+            Some((_, name, _)) => name,
+            None => "nobody",
+        }
+    }
+}
+
+impl EventSink for Narrator {
+    fn push(&mut self, event: FightEvent) {
+        match event {
+            FightEvent::TurnStarted { turn } => println!("Turn {}", turn),
+            FightEvent::RuleFired { fighter, .. } => {
+                if let Some((_, name, health)) = self.fighters.iter().find(|(id, _, _)| *id == fighter) {
+                    println!("\tTurn of {} — {}HP", name, health);
+                }
+            }
+            FightEvent::ActionChosen { action, target, .. } => {
+                println!("\t\t{} ({}).", action, self.name(&target));
+            }
+            FightEvent::Reacted { fighter, relic, action, target } => {
+                println!("\t\t{} reacts with relic {}: {} ({}).", self.name(&fighter), relic, action, self.name(&target));
+            }
+            FightEvent::ActionFailed { fighter, error } => {
+                println!("\t\t{} can't act: {:?}.", self.name(&fighter), error);
+            }
+            FightEvent::StatChanged { fighter, stat: Stat::Health, before, after } => {
+                if let Some((_, _, health)) = self.fighters.iter_mut().find(|(id, _, _)| *id == fighter) {
+                    *health = after;
+                }
+                if after < before {
+                    println!("\t\t{} lost {}HP!", self.name(&fighter), before - after);
+                } else {
+                    println!("\t\t{} gained {}HP!", self.name(&fighter), after - before);
+                }
+            }
+            FightEvent::StatChanged { fighter, stat, before, after } => {
+                println!("\t\t{} {:?} {} -> {}", self.name(&fighter), stat, before, after);
+            }
+            FightEvent::StatusApplied { fighter, status, duration, .. } => {
+                println!("\t\t{} is {:?} for {} turns!", self.name(&fighter), status, duration);
+            }
+            FightEvent::StatusTicked { fighter, status, remaining } => {
+                println!("\t\t{} suffers from {:?} ({} turns left).", self.name(&fighter), status, remaining);
+            }
+            FightEvent::StatusExpired { fighter, status } => {
+                println!("\t\t{} is no longer {:?}.", self.name(&fighter), status);
+            }
+            FightEvent::Died { fighter } => println!("\t\t{} is dead!", self.name(&fighter)),
+            FightEvent::Outcome { state } => match state {
+                State::AlliesVictory => println!("Allies won!"),
+                State::EnemiesVictory => println!("Enemies won :<"),
+                State::Draw => println!("Draw!"),
+            },
+        }
+    }
+}
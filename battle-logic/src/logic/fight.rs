@@ -1,16 +1,20 @@
 use std::cell::{Ref, RefCell, RefMut};
-use std::ops::{Deref, DerefMut};
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
 
 use crate::logic::prelude::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum State {
     AlliesVictory,
     EnemiesVictory,
     Draw,
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum FighterID {
     Ally(usize),
     Enemy(usize),
@@ -19,10 +23,7 @@ pub enum FighterID {
 
 impl FighterID {
     pub fn is_ally(&self) -> bool {
-        match self {
-            FighterID::Ally(_) => true,
-            _ => false,
-        }
+        matches!(self, FighterID::Ally(_))
     }
 }
 
@@ -30,17 +31,52 @@ pub const MAX_TURNS: u8 = 50;
 
 pub struct Fight {
     pub turn: u8,
+    /// The fight is a draw after this many turns. At least one turn is played, even with 0.
+    pub max_turns: u8,
     pub fighters: Vec<(FighterID, RefCell<Fighter>)>,
+    /// Breaks speed ties when set, see [Fight::with_seed].
+    pub(crate) rng: Option<Rng>,
+    pub(crate) pending: VecDeque<FighterID>,
+    pub(crate) outcome: Option<State>,
+}
+
+/// SplitMix64 generator, enough to shuffle fighters reproducibly.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Shuffles `items` in place.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
 }
 
 impl Fight {
-    pub fn start(team1: Vec<Fighter>, team2: Vec<Fighter>) -> State {
-        let mut fight = Fight::build_fight(team1, team2);
+    pub fn start(team1: Vec<Fighter>, team2: Vec<Fighter>, sink: &mut dyn EventSink) -> State {
+        Fight::build_fight(team1, team2).run(sink)
+    }
 
+    pub fn run(&mut self, sink: &mut dyn EventSink) -> State {
         loop {
-            match fight.turn() {
-                Some(result) => return result,
-                None => (),
+            if let Some(result) = self.turn(sink) {
+                return result;
             }
         }
     }
@@ -56,70 +92,246 @@ impl Fight {
             .enumerate()
             .for_each(|(i, f)| fighters.push((FighterID::Enemy(i), RefCell::new(f))));
 
-        Fight { turn: 0, fighters }
+        Fight { turn: 0, max_turns: MAX_TURNS, fighters, rng: None, pending: VecDeque::new(), outcome: None }
+    }
+
+    /// Limits the fight to `max_turns` turns, at least one.
+    pub fn with_max_turns(mut self, max_turns: u8) -> Fight {
+        self.max_turns = max_turns;
+        self
+    }
+
+    /// Fighters with the same speed play in a random order drawn from `seed`,
+    /// instead of alternating every turn.
+    pub fn with_seed(mut self, seed: u64) -> Fight {
+        self.rng = Some(Rng::new(seed));
+        self
+    }
+
+    /// Plays until the end of the current turn, or of the next one between turns.
+    pub fn turn(&mut self, sink: &mut dyn EventSink) -> Option<State> {
+        loop {
+            if let Some(state) = self.step(sink) {
+                return Some(state);
+            }
+            if self.pending.is_empty() {
+                return None;
+            }
+        }
+    }
+
+    /// Plays the action of the next fighter, starting a new turn if needed.
+    /// Once the fight is over, returns its outcome without doing anything.
+    pub fn step(&mut self, sink: &mut dyn EventSink) -> Option<State> {
+        if self.outcome.is_some() {
+            return self.outcome;
+        }
+        if self.pending.is_empty()
+            && let Some(state) = self.start_turn(sink)
+        {
+            return self.end(state, sink);
+        }
+
+        while let Some(id) = self.pending.pop_front() {
+            if !self.is_alive(id) { continue; };
+
+            let mut events = Vec::new();
+            self.play(id, &mut events);
+            events.iter().for_each(|event| sink.push(event.clone()));
+            self.react(&events, sink);
+
+            if let Some(state) = self.check_state() {
+                return self.end(state, sink);
+            }
+            break;
+        }
+        None
+    }
+
+    /// Iterator over the steps of the fight, see [Steps].
+    pub fn steps(&mut self) -> Steps<'_> {
+        Steps { fight: self }
+    }
+
+    /// Fighters who haven't played yet this turn, in order.
+    pub fn pending(&self) -> impl Iterator<Item = &FighterID> {
+        self.pending.iter()
+    }
+
+    /// Outcome of the fight, once over.
+    pub fn outcome(&self) -> Option<State> {
+        self.outcome
     }
 
-    pub fn turn(&mut self) -> Option<State> {
+    fn end(&mut self, state: State, sink: &mut dyn EventSink) -> Option<State> {
+        self.outcome = Some(state);
+        sink.push(FightEvent::Outcome { state });
+        Some(state)
+    }
+
+    /// Starts a new turn and decides the order in which fighters play.
+    fn start_turn(&mut self, sink: &mut dyn EventSink) -> Option<State> {
         self.turn += 1;
-        if self.turn > MAX_TURNS {
+        if self.turn > self.max_turns.max(1) {
             return Some(State::Draw);
         }
 
-        println!("Turn {}", self.turn);
+        let started = FightEvent::TurnStarted { turn: self.turn };
+        sink.push(started.clone());
+        self.react(&[started], sink);
+        if let Some(state) = self.check_state() {
+            return Some(state);
+        }
 
         // Order fighters by speed
-        self.fighters.sort_by_key(|(_, fighter)| fighter.borrow().deref().get_stat(&Stat::Speed));
-        self.fighters.reverse();
+        match &mut self.rng {
+            Some(rng) => {
+                rng.shuffle(&mut self.fighters);
+                self.fighters.sort_by_key(|(_, fighter)| Reverse(fighter.borrow().deref().get_stat(&Stat::Speed)));
+            }
+            None => {
+                self.fighters.sort_by_key(|(_, fighter)| fighter.borrow().deref().get_stat(&Stat::Speed));
+                self.fighters.reverse();
+            }
+        }
+        self.pending = self.fighters.iter().map(|(id, _)| *id).collect();
+        None
+    }
 
-        // Get turns order
-        let mut turn_order = Vec::new();
-        self.fighters
-            .iter()
-            .for_each(|(id, _)| turn_order.push(id.clone()));
+    /// Plays the turn of a fighter.
+    fn play(&self, id: FighterID, sink: &mut dyn EventSink) {
+        // Start of turn logic
+        self.update(id, sink, |fighter| fighter.turn());
+        let Some(fighter) = self.fighter(id) else { return };
+        if !fighter.is_alive() { return; }
 
-        let mut state: Option<State> = None;
+        // Resolve rule, action, target for the turn
+        let (index, rule) = fighter.get_rule(&id, self);
+        drop(fighter);
+        sink.push(FightEvent::RuleFired { fighter: id, rule: index });
+        let action = rule.action;
+        let target = action.get_target(&id, self);
+        sink.push(FightEvent::ActionChosen { fighter: id, action: action.clone(), target });
 
-        for id in turn_order {
-            let (action, target) = {
-                let mut active = self.get_fighter_mut(id);
-                if !active.is_alive() { continue; };
+        self.execute(id, &action, target, false, sink);
+    }
 
-                // Start of turn logic
-                active.turn();
+    /// Lets relics react to `events`.
+    /// Reactions are reported to `sink` but can't be reacted to, and stop once the fight is over.
+    fn react(&self, events: &[FightEvent], sink: &mut dyn EventSink) {
+        let mut triggers = Vec::new();
+        for event in events {
+            match event {
+                FightEvent::TurnStarted { .. } => self.fighters
+                    .iter()
+                    .for_each(|(id, _)| triggers.push((*id, Trigger::TurnStarted))),
+                FightEvent::StatChanged { fighter, stat: Stat::Health, before, after } if after < before => {
+                    triggers.push((*fighter, Trigger::TookDamage { amount: before - after }))
+                }
+                FightEvent::Died { fighter } => self.fighters
+                    .iter()
+                    .filter(|(id, _)| id.is_ally() == fighter.is_ally() && id != fighter)
+                    .for_each(|(id, _)| triggers.push((*id, Trigger::AllyDied))),
+                _ => {}
+            }
+        }
 
-                // Resolve rule, action, target for the turn
-                let rule = active.get_rule(&self);
-                let action = rule.action.clone();
-                let target = action.get_target(&id, &self);
-                (action, target)
+        for (id, trigger) in triggers {
+            let Some(reactions) = self.fighter(id).filter(|f| f.is_alive()).map(|f| f.react(&trigger)) else {
+                continue;
             };
+            // Buffs taken before the fighter's own turn last through it.
+            let waiting = trigger == Trigger::TurnStarted || self.pending.contains(&id);
+
+            for (relic, action) in reactions {
+                if self.check_state().is_some() { return; }
+                if !self.is_alive(id) { break; }
+
+                let target = action.get_target(&id, self);
+                sink.push(FightEvent::Reacted { fighter: id, relic, action: action.clone(), target });
+                self.execute(id, &action, target, waiting, sink);
+            }
+        }
+    }
 
-            let consequences = {
-                action.execute(self.get_fighter(id).deref(), self.get_fighter(target).deref())
+    /// Applies the consequences of an action.
+    /// With `waiting`, the buffs the fighter gives themself last one more of their turns.
+    fn execute(&self, id: FighterID, action: &Action, target: FighterID, waiting: bool, sink: &mut dyn EventSink) {
+        let consequences = match (self.fighter(id), self.fighter(target)) {
+            (Some(active), Some(passive)) => action.execute(active.deref(), passive.deref()),
+            _ => Err(ActionError::NoTarget),
+        };
+        let consequences = match consequences {
+            Ok(consequences) => consequences,
+            Err(error) => {
+                sink.push(FightEvent::ActionFailed { fighter: id, error });
+                return;
+            }
+        };
+
+        for (on_self, consequence) in consequences {
+            let (receiver, consequence) = match (on_self, consequence) {
+                (WeaponTarget::Me, Consequence::Buff { stat, amount, duration }) if waiting => {
+                    (id, Consequence::Buff { stat, amount, duration: duration + 1 })
+                }
+                (WeaponTarget::Me, consequence) => (id, consequence),
+                (WeaponTarget::Other, consequence) => (target, consequence),
             };
+            self.update(receiver, sink, |fighter| consequence.apply_on(fighter));
+        }
+    }
 
-            for (on_self, consequence) in consequences {
-                match on_self {
-                    WeaponTarget::Me => consequence.apply_on(self.get_fighter_mut(id).deref_mut()),
-                    WeaponTarget::Other => consequence.apply_on(self.get_fighter_mut(target).deref_mut()),
-                };
+    /// Applies `change` to a fighter and reports the statuses and stats it modified.
+    fn update(&self, id: FighterID, sink: &mut dyn EventSink, change: impl FnOnce(&mut Fighter)) {
+        let Some(mut fighter) = self.fighter_mut(id) else { return };
+        let before = Stat::ALL.map(|stat| fighter.get_stat(&stat));
+        let statuses = fighter.get_statuses().clone();
+        let was_alive = fighter.is_alive();
+
+        change(&mut fighter);
+
+        for old in &statuses {
+            match fighter.get_statuses().iter().find(|s| s.status == old.status) {
+                Some(new) if new.duration < old.duration => sink.push(FightEvent::StatusTicked {
+                    fighter: id, status: new.status, remaining: new.duration,
+                }),
+                Some(new) if new != old => sink.push(FightEvent::StatusApplied {
+                    fighter: id, status: new.status, duration: new.duration, potency: new.potency,
+                }),
+                Some(_) => {}
+                None => sink.push(FightEvent::StatusExpired { fighter: id, status: old.status }),
+            }
+        }
+        for new in fighter.get_statuses() {
+            if !statuses.iter().any(|s| s.status == new.status) {
+                sink.push(FightEvent::StatusApplied {
+                    fighter: id, status: new.status, duration: new.duration, potency: new.potency,
+                });
             }
+        }
 
-            state = self.check_state();
-            if state != None {
-                break;
+        for (stat, before) in Stat::ALL.into_iter().zip(before) {
+            let after = fighter.get_stat(&stat);
+            if after != before {
+                sink.push(FightEvent::StatChanged { fighter: id, stat, before, after });
             }
         }
+        if was_alive && !fighter.is_alive() {
+            sink.push(FightEvent::Died { fighter: id });
+        }
+    }
 
-        return state;
+    /// Returns the fighter with the given id, if any.
+    pub fn fighter(&self, id: FighterID) -> Option<Ref<'_, Fighter>> {
+        self.fighters.iter().find(|(f_id, _)| *f_id == id).map(|(_, f)| f.borrow())
     }
 
-    fn get_fighter(&self, id: FighterID) -> Ref<Fighter> {
-        self.fighters.iter().find(|(f_id, _)| *f_id == id).unwrap().1.borrow()
+    fn fighter_mut(&self, id: FighterID) -> Option<RefMut<'_, Fighter>> {
+        self.fighters.iter().find(|(f_id, _)| *f_id == id).map(|(_, f)| f.borrow_mut())
     }
 
-    fn get_fighter_mut(&self, id: FighterID) -> RefMut<Fighter> {
-        self.fighters.iter().find(|(f_id, _)| *f_id == id).unwrap().1.borrow_mut()
+    fn is_alive(&self, id: FighterID) -> bool {
+        self.fighter(id).is_some_and(|f| f.is_alive())
     }
 
     pub fn check_state(&self) -> Option<State> {
@@ -136,6 +348,336 @@ impl Fight {
         {
             return Some(State::EnemiesVictory);
         }
-        return None;
+        None
+    }
+}
+
+/// Plays a fight one [Fight::step] at a time, yielding the events of each step.
+/// The last step ends with [FightEvent::Outcome].
+pub struct Steps<'a> {
+    fight: &'a mut Fight,
+}
+
+impl Steps<'_> {
+    /// The fight as left by the last step.
+    pub fn fight(&self) -> &Fight {
+        self.fight
+    }
+}
+
+impl Iterator for Steps<'_> {
+    type Item = Vec<FightEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fight.outcome.is_some() {
+            return None;
+        }
+        let mut events = Vec::new();
+        self.fight.step(&mut events);
+        Some(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grammar::fight_status::{read_replay, write_fight};
+    use crate::logic::prelude::*;
+    use crate::predefined::prelude::*;
+
+    #[test]
+    fn test_events() {
+        let mut events: Vec<FightEvent> = Vec::new();
+        let state = Fight::start(
+            vec![Fighters::Arches.new(), Fighters::Arches.new()],
+            vec![Fighters::Bat.new()],
+            &mut events,
+        );
+
+        assert_eq!(events.first(), Some(&FightEvent::TurnStarted { turn: 1 }));
+        assert_eq!(events.last(), Some(&FightEvent::Outcome { state }));
+        assert!(events.contains(&FightEvent::RuleFired { fighter: FighterID::Ally(0), rule: 0 }));
+        assert!(events.contains(&FightEvent::ActionChosen {
+            fighter: FighterID::Ally(0),
+            action: Action::Attack(Target::FoeLess(Stat::Health), WeaponRef::new(WeaponKind::Sword, 1)),
+            target: FighterID::Enemy(0),
+        }));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            FightEvent::StatChanged { fighter: FighterID::Enemy(0), stat: Stat::Health, before: 60, after: 45 }
+        )));
+    }
+
+    #[test]
+    fn test_statuses() {
+        let bat = FighterID::Enemy(0);
+        let mut fight = Fight::build_fight(vec![Fighters::Arches.new()], vec![Fighters::Bat.new()]);
+        fight.fighter_mut(bat).unwrap().inflict(Status::Poisoned, 2, 4);
+        fight.fighter_mut(bat).unwrap().inflict(Status::Slow, 1, 10);
+        assert_eq!(fight.fighter(bat).unwrap().get_stat(&Stat::Speed), 0);
+
+        let mut events: Vec<FightEvent> = Vec::new();
+        fight.turn(&mut events);
+        assert!(events.contains(&FightEvent::StatusTicked { fighter: bat, status: Status::Poisoned, remaining: 1 }));
+        assert!(events.contains(&FightEvent::StatusExpired { fighter: bat, status: Status::Slow }));
+        assert!(events.contains(&FightEvent::StatChanged { fighter: bat, stat: Stat::Health, before: 60, after: 56 }));
+        assert_eq!(fight.fighter(bat).unwrap().get_stat(&Stat::Speed), 4);
+
+        fight.fighter_mut(bat).unwrap().inflict(Status::Poisoned, 1, 10);
+        assert!(fight.fighter(bat).unwrap().has_status(&Status::Poisoned));
+        events.clear();
+        fight.turn(&mut events);
+        assert!(events.contains(&FightEvent::StatusExpired { fighter: bat, status: Status::Poisoned }));
+        assert!(events.contains(&FightEvent::StatChanged { fighter: bat, stat: Stat::Health, before: 56, after: 46 }));
+        assert!(!fight.fighter(bat).unwrap().has_status(&Status::Poisoned));
+    }
+
+    #[test]
+    fn test_buffs() {
+        let arches = FighterID::Ally(0);
+        let mut defender = Fighters::Arches.new();
+        defender.set_rules(vec![Rules::Defense.new()]);
+        let mut fight = Fight::build_fight(vec![defender], vec![Fighters::Bat.new()]);
+
+        let mut events: Vec<FightEvent> = Vec::new();
+        fight.turn(&mut events);
+        assert!(events.contains(&FightEvent::StatChanged { fighter: arches, stat: Stat::Defense, before: 10, after: 20 }));
+        assert_eq!(fight.fighter(arches).unwrap().get_stat(&Stat::Defense), 20);
+
+        fight.fighter_mut(arches).unwrap().buff(Stat::Attack, 3, 1);
+        fight.fighter_mut(arches).unwrap().buff(Stat::Attack, 4, 0);
+        fight.fighter_mut(arches).unwrap().buff(Stat::Attack, -20, 2);
+        assert_eq!(fight.fighter(arches).unwrap().get_stat(&Stat::Attack), 0);
+
+        events.clear();
+        fight.turn(&mut events);
+        assert!(events.contains(&FightEvent::StatChanged { fighter: arches, stat: Stat::Defense, before: 20, after: 10 }));
+        assert!(events.contains(&FightEvent::StatChanged { fighter: arches, stat: Stat::Defense, before: 10, after: 20 }));
+        assert_eq!(fight.fighter(arches).unwrap().get_modifiers().len(), 3);
+
+        // Only the debuff remains: 5 ATK - 20
+        fight.turn(&mut Vec::new());
+        assert_eq!(fight.fighter(arches).unwrap().get_stat(&Stat::Attack), 0);
+        fight.turn(&mut Vec::new());
+        assert_eq!(fight.fighter(arches).unwrap().get_stat(&Stat::Attack), 5);
+    }
+
+    #[test]
+    fn test_spells() {
+        let mage = Fighter::new(
+            "Mage".to_string(),
+            Stats::new(30, 2, 0, 0, 6, 20),
+            vec![Rule { gate: Gate::ID(Condition::EveryXTurn(1)), action: Action::Spell(Target::FoeLess(Stat::Health), SpellRef::new(SpellKind::Fireball, 1)) }],
+            vec![],
+            Some(Box::new(Fireballs::Fireball.new())),
+        );
+        let mut fight = Fight::build_fight(vec![mage], vec![Fighters::Bat.new()]);
+
+        let mut events: Vec<FightEvent> = Vec::new();
+        fight.turn(&mut events);
+        assert!(events.contains(&FightEvent::StatusApplied {
+            fighter: FighterID::Enemy(0), status: Status::Burn, duration: 2, potency: 3,
+        }));
+        assert!(fight.fighter(FighterID::Enemy(0)).unwrap().has_status(&Status::Burn));
+    }
+
+    #[test]
+    fn test_reactions() {
+        let knight = |speed| {
+            let mut knight = Fighter::new(
+                "Knight".to_string(),
+                Stats::new(50, 10, 0, 0, 0, speed),
+                vec![Rule {
+                    gate: Gate::ID(Condition::EveryXTurn(1)),
+                    action: Action::Attack(Target::FoeLess(Stat::Health), WeaponRef::new(WeaponKind::Sword, 1)),
+                }],
+                vec![Box::new(Swords::WoodenSword.new())],
+                None,
+            );
+            knight.set_relics(vec![Box::new(Relics::Aegis.new()), Box::new(Relics::Thorns.new())]);
+            knight
+        };
+        let (a, b) = (FighterID::Ally(0), FighterID::Enemy(0));
+        let mut fight = Fight::build_fight(vec![knight(10)], vec![knight(5)]);
+
+        let mut events: Vec<FightEvent> = Vec::new();
+        fight.turn(&mut events);
+        let reactions: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                FightEvent::Reacted { fighter, relic, target, .. } => Some((*fighter, *relic, *target)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reactions, vec![(a, 0, a), (b, 0, b), (b, 1, a), (a, 1, b)]);
+        assert_eq!(fight.fighter(a).unwrap().get_stat(&Stat::Health), 10);
+        assert_eq!(fight.fighter(b).unwrap().get_stat(&Stat::Health), 10);
+    }
+
+    #[test]
+    fn test_reaction_after_victory() {
+        let attack = Rule {
+            gate: Gate::ID(Condition::EveryXTurn(1)),
+            action: Action::Attack(Target::FoeLess(Stat::Health), WeaponRef::new(WeaponKind::Sword, 1)),
+        };
+        let mut knight = Fighter::new(
+            "Knight".to_string(),
+            Stats::new(50, 10, 0, 0, 0, 1),
+            vec![Rules::Wait.new()],
+            vec![Box::new(Swords::WoodenSword.new())],
+            None,
+        );
+        knight.set_relics(vec![Box::new(Relics::Thorns.new()), Box::new(Relics::Thorns.new())]);
+        let imp = Fighter::new(
+            "Imp".to_string(),
+            Stats::new(1, 10, 0, 0, 0, 10),
+            vec![attack.clone()],
+            vec![Box::new(Swords::WoodenSword.new())],
+            None,
+        );
+
+        // The first counter-attack kills the imp, the second one has no target left.
+        let mut events: Vec<FightEvent> = Vec::new();
+        let state = Fight::start(vec![knight], vec![imp], &mut events);
+        assert_eq!(state, State::AlliesVictory);
+        assert_eq!(events.iter().filter(|e| matches!(e, FightEvent::Reacted { .. })).count(), 1);
+
+        let fight = Fight::build_fight(vec![Fighters::Arches.new()], vec![Fighters::Bat.new()]);
+        let mut events: Vec<FightEvent> = Vec::new();
+        fight.execute(FighterID::Ally(0), &attack.action, FighterID::None, false, &mut events);
+        assert_eq!(events, vec![FightEvent::ActionFailed { fighter: FighterID::Ally(0), error: ActionError::NoTarget }]);
+    }
+
+    #[test]
+    fn test_reaction_after_death() {
+        struct Fuse;
+        impl Relic for Fuse {
+            fn get_name(&self) -> String {
+                "Fuse".to_string()
+            }
+
+            fn react(&self, trigger: &Trigger) -> Option<Action> {
+                let sword = WeaponRef::new(WeaponKind::Sword, 1);
+                (*trigger == Trigger::TurnStarted).then_some(Action::Attack(Target::Them, sword))
+            }
+        }
+        let mut fuse = Fighter::new(
+            "Fuse".to_string(),
+            Stats::new(1, 10, 0, 0, 0, 10),
+            vec![Rules::Wait.new()],
+            vec![Box::new(Swords::WoodenSword.new())],
+            None,
+        );
+        fuse.set_relics(vec![Box::new(Fuse), Box::new(Fuse)]);
+        let mut knight = Fighters::Arches.new();
+        knight.set_relics(vec![Box::new(Relics::Aegis.new())]);
+        let mut fight = Fight::build_fight(vec![fuse, knight], vec![Fighters::Bat.new()]);
+
+        // The fuse blows itself up with its first relic, the knight still reacts.
+        let mut events: Vec<FightEvent> = Vec::new();
+        fight.turn(&mut events);
+        let reactions: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                FightEvent::Reacted { fighter, relic, .. } => Some((*fighter, *relic)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reactions, vec![(FighterID::Ally(0), 0), (FighterID::Ally(1), 0)]);
+        assert!(!fight.is_alive(FighterID::Ally(0)));
+    }
+
+    #[test]
+    fn test_aegis() {
+        let mut knight = Fighter::new("Knight".to_string(), Stats::new(50, 10, 5, 0, 0, 10), vec![Rules::Wait.new()], vec![], None);
+        knight.set_relics(vec![Box::new(Relics::Aegis.new())]);
+        let mut fight = Fight::build_fight(vec![knight], vec![Fighters::Bat.new()]);
+
+        // The knight plays first: the defense taken at the start of the turn lasts through theirs.
+        let mut steps = fight.steps();
+        steps.next();
+        assert_eq!(steps.fight().fighter(FighterID::Ally(0)).unwrap().get_stat(&Stat::Defense), 10);
+        steps.next();
+        steps.next();
+        assert_eq!(steps.fight().fighter(FighterID::Ally(0)).unwrap().get_stat(&Stat::Defense), 10);
+    }
+
+    #[test]
+    fn test_weapons() {
+        let iron = WeaponRef::new(WeaponKind::Sword, 2);
+        let mut arches = Fighters::Arches.new();
+        arches.set_rules(vec![
+            Rule { gate: Gate::ID(Condition::OnTurn(1)), action: Action::Attack(Target::FoeLess(Stat::Health), iron) },
+            Rules::Attack2.new(),
+        ]);
+        assert_eq!(arches.missing_weapons(), vec![iron]);
+
+        let mut fight = Fight::build_fight(vec![arches], vec![Fighters::Bat.new()]);
+        let mut events: Vec<FightEvent> = Vec::new();
+        fight.turn(&mut events);
+        assert!(events.contains(&FightEvent::ActionFailed {
+            fighter: FighterID::Ally(0),
+            error: ActionError::MissingWeapon(iron),
+        }));
+        assert_eq!(fight.fighter(FighterID::Enemy(0)).unwrap().get_stat(&Stat::Defense), 15);
+    }
+
+    #[test]
+    fn test_options() {
+        let waiting = || {
+            let mut arches = Fighters::Arches.new();
+            arches.set_rules(vec![Rules::Wait.new()]);
+            arches
+        };
+        let mut events: Vec<FightEvent> = Vec::new();
+        let state = Fight::build_fight(vec![waiting()], vec![waiting()]).with_max_turns(3).run(&mut events);
+        assert_eq!(state, State::Draw);
+        assert_eq!(events.iter().filter(|e| matches!(e, FightEvent::TurnStarted { .. })).count(), 3);
+        let log = write_fight(&mut Fight::build_fight(vec![waiting()], vec![waiting()]).with_max_turns(0)).unwrap();
+        assert!(log.starts_with("- TURN 1\n") && log.ends_with("= DRAW\n"));
+        assert!(read_replay(&log).is_ok());
+
+        // Same speed: the order only depends on the seed.
+        let order = |seed: u64| {
+            let mut events: Vec<FightEvent> = Vec::new();
+            Fight::build_fight(vec![waiting()], vec![waiting()]).with_max_turns(10).with_seed(seed).run(&mut events);
+            events
+                .iter()
+                .filter_map(|e| match e {
+                    FightEvent::RuleFired { fighter, .. } => Some(*fighter),
+                    _ => None,
+                })
+                .collect::<Vec<FighterID>>()
+        };
+        assert_eq!(order(7), order(7));
+        assert_ne!(order(7), order(8));
+        assert_eq!(order(7).len(), 20);
+    }
+
+    #[test]
+    fn test_steps() {
+        let teams = || (vec![Fighters::Arches.new(), Fighters::Arches.new()], vec![Fighters::Bat.new()]);
+        let (allies, enemies) = teams();
+        let mut events: Vec<FightEvent> = Vec::new();
+        let state = Fight::start(allies, enemies, &mut events);
+
+        let (allies, enemies) = teams();
+        let mut fight = Fight::build_fight(allies, enemies);
+        let mut steps = fight.steps();
+        let first = steps.next().unwrap();
+        assert_eq!(first[0], FightEvent::TurnStarted { turn: 1 });
+        assert_eq!(first.iter().filter(|e| matches!(e, FightEvent::ActionChosen { .. })).count(), 1);
+        assert_eq!(steps.fight().pending().count(), 2);
+
+        let mut health = steps.fight().fighter(FighterID::Enemy(0)).unwrap().get_stat(&Stat::Health);
+        let mut stepped = first;
+        while let Some(step) = steps.next() {
+            let now = steps.fight().fighter(FighterID::Enemy(0)).unwrap().get_stat(&Stat::Health);
+            assert!(now <= health);
+            health = now;
+            stepped.extend(step);
+        }
+        assert_eq!(stepped, events);
+        assert_eq!(fight.outcome(), Some(state));
+        assert_eq!(fight.steps().next(), None);
     }
 }
@@ -1,7 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 use crate::logic::prelude::*;
 use crate::predefined;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub struct Stats {
     health: u16,
     attack: u16,
@@ -16,6 +18,34 @@ impl Stats {
         Stats { health, attack, defense, nature, demon, speed }
     }
 
+    pub fn get(&self, stat: &Stat) -> u16 {
+        match stat {
+            Stat::Health => self.health,
+            Stat::Attack => self.attack,
+            Stat::Defense => self.defense,
+            Stat::Speed => self.speed,
+            Stat::Nature => self.nature,
+            Stat::Demon => self.demon,
+        }
+    }
+
+    fn get_mut(&mut self, stat: &Stat) -> &mut u16 {
+        match stat {
+            Stat::Health => &mut self.health,
+            Stat::Attack => &mut self.attack,
+            Stat::Defense => &mut self.defense,
+            Stat::Speed => &mut self.speed,
+            Stat::Nature => &mut self.nature,
+            Stat::Demon => &mut self.demon,
+        }
+    }
+
+    /// Adds `amount` to a stat, which can't go below 0.
+    pub fn add(&mut self, stat: &Stat, amount: i32) {
+        let value = self.get_mut(stat);
+        *value = (*value as i32 + amount).clamp(0, u16::MAX as i32) as u16;
+    }
+
     pub fn reset(&mut self, base: Stats) {
         self.attack = base.attack;
         self.defense = base.defense;
@@ -24,14 +54,28 @@ impl Stats {
         self.demon = base.demon;
     }
 
-    fn calc(&self, weights: StatWeights) -> u16 {
-        let product = (self.attack as i64 * weights.attack as i64 +
-            self.defense as i64 * weights.defense as i64 +
-            self.speed as i64 * weights.speed as i64 +
-            self.nature as i64 * weights.nature as i64 +
-            self.demon as i64 * weights.demon as i64) / weights.sum() as i64;
-        if product < 0 { return 0; }
-        return product as u16;
+    /// Weighted average of the stats, negative when the negative weights win.
+    fn calc(&self, weights: &StatWeights) -> i32 {
+        (self.attack as i32 * weights.attack as i32 +
+            self.defense as i32 * weights.defense as i32 +
+            self.speed as i32 * weights.speed as i32 +
+            self.nature as i32 * weights.nature as i32 +
+            self.demon as i32 * weights.demon as i32) / weights.sum() as i32
+    }
+
+    /// Lowers every stat with a positive weight by `amount`, which lowers [Stats::calc] by as much.
+    fn wear(&mut self, weights: &StatWeights, amount: u16) {
+        for (stat, weight) in [
+            (&mut self.attack, weights.attack),
+            (&mut self.defense, weights.defense),
+            (&mut self.speed, weights.speed),
+            (&mut self.nature, weights.nature),
+            (&mut self.demon, weights.demon),
+        ] {
+            if weight > 0 {
+                *stat = stat.saturating_sub(amount);
+            }
+        }
     }
 }
 
@@ -60,9 +104,31 @@ impl StatWeights {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum Status {
+    /// Loses `potency` HP at the start of each turn.
     Poisoned,
+    /// Loses `potency` SPD.
+    Slow,
+    /// Loses `potency` HP at the start of each turn.
+    Burn,
+}
+
+/// A buff (or debuff when `amount` is negative) lasting until the start of the fighter's turn
+/// once `duration` more of their turns have passed.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Modifier {
+    pub stat: Stat,
+    pub amount: i32,
+    pub duration: u8,
+}
+
+/// A status affecting a fighter for their next `duration` turns.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ActiveStatus {
+    pub status: Status,
+    pub duration: u8,
+    pub potency: u16,
 }
 
 pub struct Fighter {
@@ -72,26 +138,118 @@ pub struct Fighter {
     alive: bool,
     rules: Vec<Rule>,
     default_rule: Rule,
-    weapon: Option<Box<dyn Weapon>>,
-    // spell: Option<Weapon>,
+    statuses: Vec<ActiveStatus>,
+    modifiers: Vec<Modifier>,
+    weapons: Vec<Box<dyn Weapon>>,
+    spell: Option<Box<dyn Spell>>,
+    relics: Vec<Box<dyn Relic>>,
 }
 
 impl Fighter {
-    pub fn new(name: String, stats: Stats, rules: Vec<Rule>, weapon: Option<Box<dyn Weapon>>) -> Self {
+    pub fn new(
+        name: String,
+        stats: Stats,
+        rules: Vec<Rule>,
+        weapons: Vec<Box<dyn Weapon>>,
+        spell: Option<Box<dyn Spell>>,
+    ) -> Self {
         Fighter {
             name,
-            base_stats: stats.clone(),
-            stats: stats.clone(),
+            base_stats: stats,
+            stats,
             alive: true,
             rules,
             default_rule: predefined::rules::Rules::Wait.new(),
-            weapon,
+            statuses: vec![],
+            modifiers: vec![],
+            weapons,
+            spell,
+            relics: vec![],
         }
     }
 
     pub fn turn(&mut self) {
-        println!("\tTurn of {} — {}HP", self.name, &self.stats.health);
         self.stats.reset(self.base_stats);
+        self.tick_modifiers();
+        self.tick_statuses();
+    }
+
+    /// Removes expired modifiers and applies the others to the reset stats.
+    fn tick_modifiers(&mut self) {
+        self.modifiers.retain(|m| m.duration > 0);
+        self.modifiers.iter_mut().for_each(|m| m.duration -= 1);
+
+        for stat in Stat::ALL {
+            if stat != Stat::Health {
+                let modified = self.modified(&stat);
+                self.stats.add(&stat, modified as i32 - self.base_stats.get(&stat) as i32);
+            }
+        }
+    }
+
+    /// Base stat plus the sum of the active modifiers, clamped once,
+    /// so that modifiers stack the same way whatever their order.
+    fn modified(&self, stat: &Stat) -> u16 {
+        let amount: i32 = self.modifiers.iter().filter(|m| m.stat == *stat).map(|m| m.amount).sum();
+        (self.base_stats.get(stat) as i32 + amount).clamp(0, u16::MAX as i32) as u16
+    }
+
+    /// Adds a modifier to a stat, effective immediately.
+    /// Health modifiers heal or hurt at once, up to the maximum HP, and don't last.
+    pub fn buff(&mut self, stat: Stat, amount: i32, duration: u8) {
+        if stat == Stat::Health {
+            if amount < 0 {
+                self.lose_health(amount.unsigned_abs().min(u16::MAX as u32) as u16);
+            } else if self.alive {
+                let health = (self.stats.health as i32 + amount).min(self.base_stats.health as i32);
+                self.stats.health = health.max(self.stats.health as i32) as u16;
+            }
+            return;
+        }
+
+        // Changes in the modified stat are applied on top of the damage and statuses of the turn.
+        let before = self.modified(&stat);
+        self.modifiers.push(Modifier { stat: stat.clone(), amount, duration });
+        self.stats.add(&stat, self.modified(&stat) as i32 - before as i32);
+    }
+
+    /// Applies active statuses for the turn and removes the ones that ran out.
+    fn tick_statuses(&mut self) {
+        self.statuses.iter_mut().for_each(|s| s.duration -= 1);
+
+        for active in self.statuses.clone() {
+            match active.status {
+                Status::Poisoned | Status::Burn => self.lose_health(active.potency),
+                Status::Slow if active.duration > 0 => {
+                    self.stats.speed = self.stats.speed.saturating_sub(active.potency)
+                }
+                Status::Slow => {}
+            }
+        }
+
+        self.statuses.retain(|s| s.duration > 0);
+    }
+
+    /// Adds a status, or refreshes it to the longest duration and highest potency.
+    pub fn inflict(&mut self, status: Status, duration: u8, potency: u16) {
+        if duration == 0 { return; }
+
+        let previous = match self.statuses.iter_mut().find(|s| s.status == status) {
+            Some(active) => {
+                let previous = active.potency;
+                active.duration = active.duration.max(duration);
+                active.potency = active.potency.max(potency);
+                previous
+            }
+            None => {
+                self.statuses.push(ActiveStatus { status, duration, potency });
+                0
+            }
+        };
+
+        if status == Status::Slow {
+            self.stats.speed = self.stats.speed.saturating_sub(potency.saturating_sub(previous));
+        }
     }
 
     pub fn get_name(&self) -> &String {
@@ -99,81 +257,166 @@ impl Fighter {
     }
 
     pub fn get_stat(&self, stat: &Stat) -> u16 {
-        match stat {
-            Stat::Health => self.stats.health,
-            Stat::Attack => self.stats.attack,
-            Stat::Defense => self.stats.defense,
-            Stat::Speed => self.stats.speed,
-            Stat::Nature => self.stats.nature,
-            Stat::Demon => self.stats.demon,
+        self.stats.get(stat)
+    }
+
+    pub fn get_max_health(&self) -> u16 {
+        self.base_stats.health
+    }
+
+    pub fn has_status(&self, status: &Status) -> bool {
+        self.statuses.iter().any(|s| s.status == *status)
+    }
+
+    pub fn get_statuses(&self) -> &Vec<ActiveStatus> {
+        &self.statuses
+    }
+
+    pub fn get_modifiers(&self) -> &Vec<Modifier> {
+        &self.modifiers
+    }
+
+    pub fn get_base_stats(&self) -> &Stats {
+        &self.base_stats
+    }
+
+    pub fn get_rules(&self) -> &Vec<Rule> {
+        &self.rules
+    }
+
+    /// Returns the first rule whose gate passes, with its index.
+    /// The default rule is indexed after the fighter's rules.
+    pub fn get_rule(&self, id: &FighterID, status: &Fight) -> (usize, Rule) {
+        match self.rules.iter().position(|rule| rule.gate.check(id, status)) {
+            Some(index) => (index, self.rules[index].clone()),
+            None => (self.rules.len(), self.default_rule.clone()),
         }
     }
 
-    pub fn get_rule(&self, status: &Fight) -> Rule {
-        return match self.rules.iter().find(|rule| rule.gate.check(status)) {
-            Some(rule) => rule.clone(),
-            None => self.default_rule.clone(),
-        };
+    pub fn get_weapon(&self, reference: &WeaponRef) -> Option<&dyn Weapon> {
+        self.weapons
+            .iter()
+            .find(|w| w.get_kind() == reference.kind && w.get_level() == reference.level)
+            .map(|w| w.as_ref())
+    }
+
+    pub fn get_weapons(&self) -> &Vec<Box<dyn Weapon>> {
+        &self.weapons
+    }
+
+    /// Returns the weapons referenced by the fighter's rules that they don't own.
+    pub fn missing_weapons(&self) -> Vec<WeaponRef> {
+        let mut missing: Vec<WeaponRef> = vec![];
+        for rule in &self.rules {
+            if let Action::Attack(_, reference) = &rule.action
+                && self.get_weapon(reference).is_none()
+                && !missing.contains(reference)
+            {
+                missing.push(*reference);
+            }
+        }
+        missing
+    }
+
+    pub fn get_spell(&self) -> Option<&dyn Spell> {
+        self.spell.as_deref()
     }
 
-    pub fn get_weapon(&self) -> Option<&dyn Weapon> { match &self.weapon {
-        None => None,
-        Some(b) => Some(b.as_ref()),
-    }}
+    pub fn get_relics(&self) -> &Vec<Box<dyn Relic>> {
+        &self.relics
+    }
+
+    pub fn set_relics(&mut self, relics: Vec<Box<dyn Relic>>) {
+        self.relics = relics;
+    }
+
+    /// Returns the actions triggered by the fighter's relics, with the relic indexes.
+    pub fn react(&self, trigger: &Trigger) -> Vec<(usize, Action)> {
+        self.relics
+            .iter()
+            .enumerate()
+            .filter_map(|(i, relic)| relic.react(trigger).map(|action| (i, action)))
+            .collect()
+    }
 
     pub fn set_rules(&mut self, rules: Vec<Rule>) {
         self.rules = rules;
     }
 
+    /// State that changes during a fight, see [Snapshot].
+    pub fn snapshot(&self, id: FighterID) -> FighterSnapshot {
+        FighterSnapshot {
+            id,
+            name: self.name.clone(),
+            stats: self.stats,
+            alive: self.alive,
+            statuses: self.statuses.clone(),
+            modifiers: self.modifiers.clone(),
+        }
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: &FighterSnapshot) {
+        self.stats = snapshot.stats;
+        self.alive = snapshot.alive;
+        self.statuses = snapshot.statuses.clone();
+        self.modifiers = snapshot.modifiers.clone();
+    }
+
     pub fn is_alive(&self) -> bool {
         self.alive
     }
 
-    pub(crate) fn damage(&mut self, amount: u16) {
-        let damage;
+    /// Takes an attack of the given element.
+    ///
+    /// A positive elemental defense shields the fighter: the blocked damage wears down every stat
+    /// the shield is made of until the end of the turn.
+    /// A negative one is a weakness to the element and adds to the damage.
+    pub(crate) fn damage(&mut self, amount: u16, element: &Element) {
+        let weights = Fighter::defense_weights(element);
+        let defense = self.stats.calc(&weights);
 
-        if self.stats.defense > 0 {
-            // Shield
-            if self.stats.defense > amount {
-                // Full block
-                damage = 0;
-                self.stats.defense -= amount;
-            } else {
-                damage = amount - self.stats.defense;
-                self.stats.defense = 0;
-            }
+        let damage = if defense >= 0 {
+            let blocked = amount.min(defense.min(u16::MAX as i32) as u16);
+            self.stats.wear(&weights, blocked);
+            amount - blocked
         } else {
-            // No shield
-            damage = amount
+            amount.saturating_add(defense.unsigned_abs().min(u16::MAX as u32) as u16)
         };
 
-        if damage >= self.stats.health {
-            println!("\t\t{} lost {}HP…", &self.name, self.stats.health);
+        self.lose_health(damage);
+    }
+
+    fn lose_health(&mut self, amount: u16) {
+        if amount >= self.stats.health {
             self.stats.health = 0;
             self.alive = false;
-            println!("\t\t{} is dead!", &self.name);
         } else {
-            self.stats.health -= damage;
-            println!("\t\t{} lost {}HP!", &self.name, damage);
+            self.stats.health -= amount;
         }
     }
 
     pub fn calc_attack(&self, element: &Element) -> u16 {
-        match element {
-            Element::Neutral => self.stats.calc(StatWeights::new(4, 0, 0, 0, 0)),
-            Element::Natural => self.stats.calc(StatWeights::new(4, 0, 4, -1, 0)),
-            Element::Demonic => self.stats.calc(StatWeights::new(4, 0, -1, 4, 0)),
-        }
+        let weights = match element {
+            Element::Neutral => StatWeights::new(4, 0, 0, 0, 0),
+            Element::Natural => StatWeights::new(4, 0, 4, -1, 0),
+            Element::Demonic => StatWeights::new(4, 0, -1, 4, 0),
+        };
+        self.stats.calc(&weights).clamp(0, u16::MAX as i32) as u16
     }
 
-    pub fn calc_defense(&self, element: &Element) -> u16 {
+    fn defense_weights(element: &Element) -> StatWeights {
         match element {
-            Element::Neutral => self.stats.calc(StatWeights::new(0, 1, 0, 0, 0)),
-            Element::Natural => self.stats.calc(StatWeights::new(0, 4, 2, -2, 0)),
-            Element::Demonic => self.stats.calc(StatWeights::new(0, 4, -2, 2, 0)),
+            Element::Neutral => StatWeights::new(0, 1, 0, 0, 0),
+            Element::Natural => StatWeights::new(0, 4, 2, -2, 0),
+            Element::Demonic => StatWeights::new(0, 4, -2, 2, 0),
         }
     }
 
+    /// Shield against the element, or weakness to it when negative.
+    pub fn calc_defense(&self, element: &Element) -> i32 {
+        self.stats.calc(&Fighter::defense_weights(element))
+    }
+
     pub fn defense(&self) -> Consequence {
         Consequence::Buff {
             stat: Stat::Defense,
@@ -182,3 +425,79 @@ impl Fighter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::logic::prelude::*;
+    use crate::predefined::prelude::*;
+
+    #[test]
+    fn test_elemental_defense() {
+        // 15 DEF, 5 NAT, 8 DEM
+        let mut bat = Fighters::Bat.new();
+        assert_eq!(bat.calc_defense(&Element::Neutral), 15);
+        assert_eq!(bat.calc_defense(&Element::Natural), 9);
+        assert_eq!(bat.calc_defense(&Element::Demonic), 11);
+
+        bat.damage(20, &Element::Neutral);
+        assert_eq!(bat.get_stat(&Stat::Health), 55);
+        assert_eq!(bat.get_stat(&Stat::Defense), 0);
+
+        bat.turn();
+        bat.damage(20, &Element::Natural);
+        assert_eq!(bat.get_stat(&Stat::Health), 44);
+        assert_eq!(bat.get_stat(&Stat::Defense), 6);
+
+        // 0 DEF, 10 NAT
+        let mut druid = Fighter::new("Druid".to_string(), Stats::new(50, 0, 0, 10, 0, 0), vec![], vec![], None);
+        druid.damage(10, &Element::Natural);
+        assert_eq!(druid.get_stat(&Stat::Health), 43);
+        assert_eq!(druid.get_stat(&Stat::Nature), 7);
+        // Weak to demons
+        druid.damage(10, &Element::Demonic);
+        assert_eq!(druid.get_stat(&Stat::Health), 31);
+    }
+
+    #[test]
+    fn test_worn_shield() {
+        // 0 DEF, 10 NAT: the shield gets weaker with every hit of the turn.
+        let mut druid = Fighter::new("Druid".to_string(), Stats::new(50, 0, 0, 10, 0, 0), vec![], vec![], None);
+        let mut health = vec![];
+        for _ in 0..3 {
+            druid.damage(10, &Element::Natural);
+            health.push(druid.get_stat(&Stat::Health));
+        }
+        assert_eq!(health, vec![43, 35, 26]);
+
+        druid.turn();
+        assert_eq!(druid.calc_defense(&Element::Natural), 3);
+    }
+
+    #[test]
+    fn test_weakness() {
+        // 0 DEF, 10 DEM
+        let mut imp = Fighter::new("Imp".to_string(), Stats::new(50, 0, 0, 0, 10, 0), vec![], vec![], None);
+        assert_eq!(imp.calc_defense(&Element::Natural), -3);
+        imp.damage(10, &Element::Natural);
+        assert_eq!(imp.get_stat(&Stat::Health), 37);
+        imp.damage(10, &Element::Demonic);
+        assert_eq!(imp.get_stat(&Stat::Health), 30);
+    }
+
+    #[test]
+    fn test_stacked_modifiers() {
+        // 5 ATK
+        let attack = |amounts: &[i32]| {
+            let mut fighter = Fighters::Arches.new();
+            amounts.iter().for_each(|amount| fighter.buff(Stat::Attack, *amount, 1));
+            let now = fighter.get_stat(&Stat::Attack);
+            fighter.turn();
+            (now, fighter.get_stat(&Stat::Attack))
+        };
+        assert_eq!(attack(&[-20, 10]), (0, 0));
+        assert_eq!(attack(&[10, -20]), (0, 0));
+        assert_eq!(attack(&[-20, 10, 30]), (25, 25));
+        assert_eq!(attack(&[30, -20, 10]), (25, 25));
+        assert_eq!(attack(&[-3, 4]), (6, 6));
+    }
+}
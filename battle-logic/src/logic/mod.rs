@@ -1,11 +1,15 @@
 pub mod equipment;
+pub mod event;
 pub mod fight;
 pub mod fighter;
 pub mod rule;
+pub mod snapshot;
 
 pub mod prelude {
     pub use crate::logic::equipment::*;
+    pub use crate::logic::event::*;
     pub use crate::logic::fight::*;
     pub use crate::logic::fighter::*;
     pub use crate::logic::rule::*;
+    pub use crate::logic::snapshot::*;
 }
\ No newline at end of file
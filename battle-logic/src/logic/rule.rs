@@ -1,5 +1,8 @@
 use std::fmt::Debug;
 use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
 use crate::logic::prelude::*;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -8,16 +11,17 @@ pub struct Rule {
     pub action: Action,
 }
 
+/// Boolean expression over conditions, [Gate::ID] being the leaves.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Gate {
     ID(Condition),
-    NOT(Condition),
-    AND(Condition, Condition),
-    NAND(Condition, Condition),
-    OR(Condition, Condition),
-    XOR(Condition, Condition),
-    NOR(Condition, Condition),
-    NXOR(Condition, Condition),
+    NOT(Box<Gate>),
+    AND(Box<Gate>, Box<Gate>),
+    NAND(Box<Gate>, Box<Gate>),
+    OR(Box<Gate>, Box<Gate>),
+    XOR(Box<Gate>, Box<Gate>),
+    NOR(Box<Gate>, Box<Gate>),
+    NXOR(Box<Gate>, Box<Gate>),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -30,14 +34,50 @@ pub enum Condition {
     HasStatus(Target, Status),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Action {
-    Attack(Target),
+    Attack(Target, WeaponRef),
+    Spell(Target, SpellRef),
     Defense,
     Wait,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Names one of the weapons of a fighter.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeaponRef {
+    pub kind: WeaponKind,
+    pub level: u8,
+}
+
+impl WeaponRef {
+    pub fn new(kind: WeaponKind, level: u8) -> Self {
+        WeaponRef { kind, level }
+    }
+}
+
+/// Names the spell of a fighter.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpellRef {
+    pub kind: SpellKind,
+    pub level: u8,
+}
+
+impl SpellRef {
+    pub fn new(kind: SpellKind, level: u8) -> Self {
+        SpellRef { kind, level }
+    }
+}
+
+/// Error raised when an action can't be performed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ActionError {
+    MissingWeapon(WeaponRef),
+    MissingSpell(SpellRef),
+    /// The target is gone, e.g. the last foe died before a reaction.
+    NoTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Target {
     Them,
     AllyMost(Stat),
@@ -46,7 +86,7 @@ pub enum Target {
     FoeLess(Stat),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Stat {
     Health,
     Attack,
@@ -56,34 +96,84 @@ pub enum Stat {
     Demon,
 }
 
+impl Stat {
+    pub const ALL: [Stat; 6] = [Stat::Health, Stat::Attack, Stat::Defense, Stat::Nature, Stat::Demon, Stat::Speed];
+}
+
+impl From<Condition> for Gate {
+    fn from(condition: Condition) -> Self {
+        Gate::ID(condition)
+    }
+}
+
 impl Gate {
-    pub fn check(&self, status: &Fight) -> bool {
+    pub fn not(gate: impl Into<Gate>) -> Gate {
+        Gate::NOT(Box::new(gate.into()))
+    }
+
+    pub fn and(g1: impl Into<Gate>, g2: impl Into<Gate>) -> Gate {
+        Gate::AND(Box::new(g1.into()), Box::new(g2.into()))
+    }
+
+    pub fn nand(g1: impl Into<Gate>, g2: impl Into<Gate>) -> Gate {
+        Gate::NAND(Box::new(g1.into()), Box::new(g2.into()))
+    }
+
+    pub fn or(g1: impl Into<Gate>, g2: impl Into<Gate>) -> Gate {
+        Gate::OR(Box::new(g1.into()), Box::new(g2.into()))
+    }
+
+    pub fn xor(g1: impl Into<Gate>, g2: impl Into<Gate>) -> Gate {
+        Gate::XOR(Box::new(g1.into()), Box::new(g2.into()))
+    }
+
+    pub fn nor(g1: impl Into<Gate>, g2: impl Into<Gate>) -> Gate {
+        Gate::NOR(Box::new(g1.into()), Box::new(g2.into()))
+    }
+
+    pub fn nxor(g1: impl Into<Gate>, g2: impl Into<Gate>) -> Gate {
+        Gate::NXOR(Box::new(g1.into()), Box::new(g2.into()))
+    }
+
+    /// Checks the gate for the rules of the `active` fighter.
+    pub fn check(&self, active: &FighterID, status: &Fight) -> bool {
+        let check = |gate: &Gate| gate.check(active, status);
         match self {
-            Gate::ID(cond) => cond.check(status),
-            Gate::NOT(cond) => !cond.check(status),
-            Gate::AND(cond1, cond2) => cond1.check(status) && cond2.check(status),
-            Gate::NAND(cond1, cond2) => !(cond1.check(status) && cond2.check(status)),
-            Gate::OR(cond1, cond2) => cond1.check(status) || cond2.check(status),
-            Gate::XOR(cond1, cond2) => {
-                (cond1.check(status) && !cond2.check(status))
-                    || (!cond1.check(status) && cond2.check(status))
-            }
-            Gate::NOR(cond1, cond2) => !(cond1.check(status) || cond2.check(status)),
-            Gate::NXOR(cond1, cond2) => (!cond1.check(status) && !cond2.check(status))
-                || (cond1.check(status) || cond2.check(status)),
+            Gate::ID(cond) => cond.check(active, status),
+            Gate::NOT(gate) => !check(gate),
+            Gate::AND(g1, g2) => check(g1) && check(g2),
+            Gate::NAND(g1, g2) => !(check(g1) && check(g2)),
+            Gate::OR(g1, g2) => check(g1) || check(g2),
+            Gate::XOR(g1, g2) => check(g1) != check(g2),
+            Gate::NOR(g1, g2) => !(check(g1) || check(g2)),
+            Gate::NXOR(g1, g2) => check(g1) == check(g2),
         }
     }
 }
 
 impl Condition {
-    pub fn check(&self, status: &Fight) -> bool {
+    /// Checks the condition, targets being resolved from the `active` fighter.
+    ///
+    /// HP thresholds are percentages of the target's maximum HP.
+    pub fn check(&self, active: &FighterID, status: &Fight) -> bool {
         match self {
-            Condition::EveryXTurn(x) => status.turn % x == 0,
+            // No turn is a multiple of zero: `EXT 0` never fires, and the analyzer reports it.
+            Condition::EveryXTurn(0) => false,
+            Condition::EveryXTurn(x) => status.turn.is_multiple_of(*x),
             Condition::OnTurn(turn) => status.turn == *turn,
             Condition::FromTurnX(turn) => status.turn >= *turn,
-            Condition::LessXHP(_, _) => true,
-            Condition::MoreXHP(_, _) => true,
-            Condition::HasStatus(_, _) => true,
+            Condition::LessXHP(x, target) => match status.fighter(target.resolve(active, status)) {
+                Some(f) => (f.get_stat(&Stat::Health) as u32) * 100 < *x as u32 * f.get_max_health() as u32,
+                None => false,
+            },
+            Condition::MoreXHP(x, target) => match status.fighter(target.resolve(active, status)) {
+                Some(f) => (f.get_stat(&Stat::Health) as u32) * 100 > *x as u32 * f.get_max_health() as u32,
+                None => false,
+            },
+            Condition::HasStatus(target, s) => match status.fighter(target.resolve(active, status)) {
+                Some(f) => f.has_status(s),
+                None => false,
+            },
         }
     }
 }
@@ -94,35 +184,35 @@ impl Target {
         let allies = fight
             .fighters
             .iter()
-            .filter(|(id, _)| id.is_ally() == active.is_ally());
+            .filter(|(id, f)| id.is_ally() == active.is_ally() && f.borrow().is_alive());
         let enemies = fight
             .fighters
             .iter()
-            .filter(|(id, _)| id.is_ally() != active.is_ally());
+            .filter(|(id, f)| id.is_ally() != active.is_ally() && f.borrow().is_alive());
 
         match self {
-            Target::Them => active.clone(),
+            Target::Them => *active,
             Target::AllyMost(stat) => {
                 match allies.max_by_key(|(_, f)| f.borrow().deref().get_stat(stat)) {
-                    Some((id, _)) => id.clone(),
+                    Some((id, _)) => *id,
                     None => FighterID::None,
                 }
             }
             Target::AllyLess(stat) => {
                 match allies.min_by_key(|(_, f)| f.borrow().deref().get_stat(stat)) {
-                    Some((id, _)) => id.clone(),
+                    Some((id, _)) => *id,
                     None => FighterID::None,
                 }
             }
             Target::FoeMost(stat) => {
                 match enemies.max_by_key(|(_, f)| f.borrow().deref().get_stat(stat)) {
-                    Some((id, _)) => id.clone(),
+                    Some((id, _)) => *id,
                     None => FighterID::None,
                 }
             }
             Target::FoeLess(stat) => {
                 match enemies.min_by_key(|(_, f)| f.borrow().deref().get_stat(stat)) {
-                    Some((id, _)) => id.clone(),
+                    Some((id, _)) => *id,
                     None => FighterID::None,
                 }
             }
@@ -131,36 +221,94 @@ impl Target {
 }
 
 impl Action {
-    pub fn name(&self) -> String {
-        match self {
-            Action::Attack(target) => format!("Attack {:?}", target),
-            Action::Defense => "Defense".to_string(),
-            Action::Wait => "Wait".to_string(),
-        }
-    }
     pub fn get_target(&self, active: &FighterID, fight: &Fight) -> FighterID {
         match self {
-            Action::Wait | Action::Defense => active.clone(),
-            Action::Attack(target) => target.resolve(active, fight),
+            Action::Wait | Action::Defense => *active,
+            Action::Attack(target, _) | Action::Spell(target, _) => target.resolve(active, fight),
         }
     }
 
-    pub fn execute(&self, active: &Fighter, target: &Fighter) -> Vec<(WeaponTarget, Consequence)> {
-        println!(
-            "\t\t{:} ({:}).",
-            self.name(),
-            target.get_name()
-        );
+    pub fn execute(&self, active: &Fighter, target: &Fighter) -> Result<Vec<(WeaponTarget, Consequence)>, ActionError> {
         let mut consequences = vec![];
         match self {
             Action::Wait => (),
             Action::Defense => consequences.push((WeaponTarget::Me, active.defense())),
-            Action::Attack(_) => {
-                if let Some(weapon) = active.get_weapon() {
-                    consequences.append(&mut weapon.use_weapon(active, target))
+            Action::Attack(_, reference) => match active.get_weapon(reference) {
+                Some(weapon) => consequences.append(&mut weapon.use_weapon(active, target)),
+                None => return Err(ActionError::MissingWeapon(*reference)),
+            },
+            Action::Spell(_, reference) => match active.get_spell() {
+                Some(spell) if (spell.get_kind(), spell.get_level()) == (reference.kind, reference.level) => {
+                    consequences.append(&mut spell.cast(active, target))
                 }
+                _ => return Err(ActionError::MissingSpell(*reference)),
             },
         }
-        return consequences;
+        Ok(consequences)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::ops::DerefMut;
+
+    use crate::logic::prelude::*;
+    use crate::predefined::prelude::*;
+
+    #[test]
+    fn test_health_conditions() {
+        let mut fight = Fight::build_fight(vec![Fighters::Arches.new()], vec![Fighters::Bat.new()]);
+        fight.turn = 2;
+        let arches = FighterID::Ally(0);
+        let careful = Rules::Careful.new().gate;
+
+        assert!(!careful.check(&arches, &fight));
+        assert!(Condition::MoreXHP(99, Target::Them).check(&arches, &fight));
+        assert!(!Condition::LessXHP(30, Target::FoeLess(Stat::Health)).check(&arches, &fight));
+        assert!(!Condition::HasStatus(Target::Them, Status::Poisoned).check(&arches, &fight));
+
+        // Arches has 100 HP and 10 DEF
+        fight.fighters[0].1.borrow_mut().deref_mut().damage(85, &Element::Neutral);
+        assert!(careful.check(&arches, &fight));
+        assert!(!careful.check(&FighterID::Enemy(0), &fight));
+        assert!(Condition::LessXHP(30, Target::FoeLess(Stat::Health)).check(&FighterID::Enemy(0), &fight));
+        assert!(!Condition::MoreXHP(25, Target::Them).check(&arches, &fight));
+    }
+
+    #[test]
+    fn test_every_x_turn() {
+        let mut fight = Fight::build_fight(vec![Fighters::Arches.new()], vec![Fighters::Bat.new()]);
+        let arches = FighterID::Ally(0);
+        for turn in 0..=6 {
+            fight.turn = turn;
+            assert!(!Condition::EveryXTurn(0).check(&arches, &fight));
+            assert_eq!(Condition::EveryXTurn(3).check(&arches, &fight), turn % 3 == 0);
+        }
+    }
+
+    #[test]
+    fn test_gates() {
+        let mut fight = Fight::build_fight(vec![Fighters::Arches.new()], vec![Fighters::Bat.new()]);
+        let arches = FighterID::Ally(0);
+        // Turns 1 to 4 give (a, b) = (false, false), (false, true), (true, false), (true, true).
+        let (a, b) = (Condition::FromTurnX(3), Condition::EveryXTurn(2));
+
+        for (gate, expected) in [
+            (Gate::ID(a.clone()), [false, false, true, true]),
+            (Gate::not(a.clone()), [true, true, false, false]),
+            (Gate::and(a.clone(), b.clone()), [false, false, false, true]),
+            (Gate::nand(a.clone(), b.clone()), [true, true, true, false]),
+            (Gate::or(a.clone(), b.clone()), [false, true, true, true]),
+            (Gate::nor(a.clone(), b.clone()), [true, false, false, false]),
+            (Gate::xor(a.clone(), b.clone()), [false, true, true, false]),
+            (Gate::nxor(a.clone(), b.clone()), [true, false, false, true]),
+        ] {
+            let values = (1..=4)
+                .map(|turn| {
+                    fight.turn = turn;
+                    gate.check(&arches, &fight)
+                })
+                .collect::<Vec<bool>>();
+            assert_eq!(values, expected, "{}", gate);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::logic::prelude::*;
+
+/// State of a fight at some point, see [Fight::snapshot].
+///
+/// Rules, equipment and base stats don't change during a fight and aren't part of it:
+/// a snapshot is restored into a fight between the same teams.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub turn: u8,
+    pub max_turns: u8,
+    /// Fighters in their current order, which breaks speed ties.
+    pub fighters: Vec<FighterSnapshot>,
+    pub rng: Option<Rng>,
+    pub pending: Vec<FighterID>,
+    pub outcome: Option<State>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FighterSnapshot {
+    pub id: FighterID,
+    pub name: String,
+    pub stats: Stats,
+    pub alive: bool,
+    pub statuses: Vec<ActiveStatus>,
+    pub modifiers: Vec<Modifier>,
+}
+
+/// Error raised when a snapshot doesn't fit the fighters of a fight.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SnapshotError {
+    MissingFighter { fighter: FighterID },
+    UnknownFighter { fighter: FighterID },
+    DuplicateFighter { fighter: FighterID },
+    WrongFighter { fighter: FighterID, expected: String, found: String },
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::MissingFighter { fighter } => write!(f, "{:?} is missing from the snapshot", fighter),
+            SnapshotError::UnknownFighter { fighter } => write!(f, "{:?} isn't in the fight", fighter),
+            SnapshotError::DuplicateFighter { fighter } => write!(f, "{:?} is in the snapshot more than once", fighter),
+            SnapshotError::WrongFighter { fighter, expected, found } => {
+                write!(f, "{:?} is {} in the fight but {} in the snapshot", fighter, expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Snapshot {
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Snapshot> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+impl Fight {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            turn: self.turn,
+            max_turns: self.max_turns,
+            fighters: self.fighters.iter().map(|(id, f)| f.borrow().snapshot(*id)).collect(),
+            rng: self.rng,
+            pending: self.pending.iter().copied().collect(),
+            outcome: self.outcome,
+        }
+    }
+
+    /// Brings the fight back to the state of `snapshot`, taken from a fight between the same teams.
+    /// The fight is left untouched on error.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        for (id, fighter) in &self.fighters {
+            let found = snapshot.fighters.iter().find(|f| f.id == *id);
+            let found = found.ok_or(SnapshotError::MissingFighter { fighter: *id })?;
+            let expected = fighter.borrow().get_name().clone();
+            if found.name != expected {
+                return Err(SnapshotError::WrongFighter { fighter: *id, expected, found: found.name.clone() });
+            }
+        }
+        let ids = snapshot.fighters.iter().map(|f| f.id).chain(snapshot.pending.iter().copied());
+        for id in ids {
+            if !self.fighters.iter().any(|(f_id, _)| *f_id == id) {
+                return Err(SnapshotError::UnknownFighter { fighter: id });
+            }
+        }
+        for (i, state) in snapshot.fighters.iter().enumerate() {
+            if snapshot.fighters[..i].iter().any(|f| f.id == state.id) {
+                return Err(SnapshotError::DuplicateFighter { fighter: state.id });
+            }
+        }
+
+        self.turn = snapshot.turn;
+        self.max_turns = snapshot.max_turns;
+        self.rng = snapshot.rng;
+        self.pending = snapshot.pending.iter().copied().collect();
+        self.outcome = snapshot.outcome;
+        self.fighters.sort_by_key(|(id, _)| snapshot.fighters.iter().position(|f| f.id == *id));
+        for (id, fighter) in &self.fighters {
+            if let Some(state) = snapshot.fighters.iter().find(|f| f.id == *id) {
+                fighter.borrow_mut().restore(state);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logic::prelude::*;
+    use crate::predefined::prelude::*;
+
+    fn new_fight() -> Fight {
+        let mut arches = Fighters::Arches.new();
+        arches.set_relics(vec![Box::new(Relics::Thorns.new())]);
+        Fight::build_fight(vec![arches, Fighters::Arches.new()], vec![Fighters::Bat.new()]).with_seed(3)
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut fight = new_fight();
+        let mut steps = fight.steps();
+        steps.nth(3);
+        let snapshot = steps.fight().snapshot();
+        let rest = steps.flatten().collect::<Vec<FightEvent>>();
+        let end = fight.snapshot();
+        assert!(snapshot.outcome.is_none() && snapshot.turn > 1);
+
+        // Undo
+        fight.restore(&snapshot).unwrap();
+        assert_eq!(fight.snapshot(), snapshot);
+        assert_eq!(fight.steps().flatten().collect::<Vec<FightEvent>>(), rest);
+        assert_eq!(fight.snapshot(), end);
+
+        // Branch, through the disk
+        let path = std::env::temp_dir().join(format!("battle-logic-snapshot-{}.json", std::process::id()));
+        snapshot.save(&path).unwrap();
+        let mut branch = new_fight();
+        branch.restore(&Snapshot::load(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(branch.steps().flatten().collect::<Vec<FightEvent>>(), rest);
+
+        let mut other = Fight::build_fight(vec![Fighters::Bat.new()], vec![Fighters::Bat.new()]);
+        assert_eq!(other.restore(&snapshot), Err(SnapshotError::WrongFighter {
+            fighter: FighterID::Ally(0),
+            expected: "Bat".to_string(),
+            found: "Arches".to_string(),
+        }));
+        let mut smaller = Fight::build_fight(vec![Fighters::Arches.new()], vec![Fighters::Bat.new()]);
+        assert_eq!(smaller.restore(&snapshot), Err(SnapshotError::UnknownFighter { fighter: FighterID::Ally(1) }));
+        assert_eq!(smaller.snapshot().turn, 0);
+
+        let mut duplicated = snapshot.clone();
+        duplicated.fighters.push(duplicated.fighters[0].clone());
+        let mut same = new_fight();
+        let fighter = duplicated.fighters[0].id;
+        assert_eq!(same.restore(&duplicated), Err(SnapshotError::DuplicateFighter { fighter }));
+        assert_eq!(same.snapshot().turn, 0);
+    }
+}
@@ -1,17 +1,258 @@
-#![allow(dead_code)]
+use std::io::Read;
+use std::process::ExitCode;
+use std::{env, fs, io};
 
-mod api;
-mod logic;
-mod predefined;
-mod grammar;
+use battle_logic::api::analyzer::{analyze_fighter, Severity};
+use battle_logic::api::protocol::serve;
+use battle_logic::api::verifier::verify_fight;
+use battle_logic::grammar::fighters_info::MAX_FIGHTERS;
+use battle_logic::prelude::*;
+use serde_json::json;
 
-use logic::prelude::*;
-use predefined::prelude::*;
+const USAGE: &str = "\
+Usage: battle-logic [run] [FILE] [--format status|narrative|json] [--turns N] [--seed SEED]
+       battle-logic check [FILE] [--turns N]
+       battle-logic verify FILE LOG [--turns N] [--seed SEED]
+       battle-logic serve
 
-fn main() {
-    match Fight::start(vec![Fighters::Arches.new(), Fighters::Arches.new()], vec![Fighters::Bat.new()]) {
-        State::AlliesVictory => println!("Allies won!"),
-        State::EnemiesVictory => println!("Enemies won :<"),
-        State::Draw => println!("Draw!"),
+Reads a fighters_info description from FILE, or from stdin when FILE is missing or `-`.
+`run` prints the fight, `check` only validates the description.
+`verify` runs the fight again and compares it with the fight_status LOG.
+`serve` answers JSON requests read line by line on stdin, see the readme.
+
+Exit codes: 0 allies won, valid input or matching log, 1 enemies won or diverging log, 2 draw,
+            64 bad usage, 65 invalid input, 66 unreadable input.";
+
+const USAGE_ERROR: u8 = 64;
+const INVALID_INPUT: u8 = 65;
+const UNREADABLE_INPUT: u8 = 66;
+
+#[derive(PartialEq)]
+enum Command {
+    Run,
+    Check,
+    Verify,
+    Serve,
+}
+
+enum Format {
+    Status,
+    Narrative,
+    Json,
+}
+
+struct Options {
+    command: Command,
+    file: Option<String>,
+    log: Option<String>,
+    format: Format,
+    turns: Option<u8>,
+    seed: Option<u64>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options { command: Command::Run, file: None, log: None, format: Format::Status, turns: None, seed: None };
+    let mut first = true;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "run" if first => options.command = Command::Run,
+            "check" if first => options.command = Command::Check,
+            "verify" if first => options.command = Command::Verify,
+            "serve" if first => options.command = Command::Serve,
+            "--format" => options.format = match value("--format")?.as_str() {
+                "status" => Format::Status,
+                "narrative" => Format::Narrative,
+                "json" => Format::Json,
+                format => return Err(format!("unknown format: {}", format)),
+            },
+            "--turns" => {
+                let turns = value("--turns")?;
+                match turns.parse() {
+                    Ok(0) | Err(_) => return Err(format!("invalid turn limit: {}, expected 1 to 255", turns)),
+                    Ok(turns) => options.turns = Some(turns),
+                }
+            }
+            "--seed" => {
+                let seed = value("--seed")?;
+                options.seed = Some(seed.parse().map_err(|_| format!("invalid seed: {}", seed))?);
+            }
+            "-h" | "--help" => return Err(String::new()),
+            option if option.starts_with("--") => return Err(format!("unknown option: {}", option)),
+            _ if options.file.is_none() => options.file = Some(arg),
+            _ if options.command == Command::Verify && options.log.is_none() => options.log = Some(arg),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+        first = false;
+    }
+    if options.command == Command::Check && options.seed.is_some() {
+        return Err("check doesn't run the fight".to_string());
+    }
+    if options.command == Command::Verify && options.log.is_none() {
+        return Err("verify needs a fighters_info FILE and a LOG".to_string());
+    }
+    if options.command == Command::Serve && (options.file.is_some() || options.turns.is_some() || options.seed.is_some()) {
+        return Err("serve reads its fights from the requests".to_string());
+    }
+    Ok(options)
+}
+
+fn read_input(file: &Option<String>) -> io::Result<String> {
+    match file.as_deref() {
+        None | Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+        Some(path) => fs::read_to_string(path),
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) if error.is_empty() => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+
+    if options.command == Command::Serve {
+        return match serve(io::stdin().lock(), io::stdout().lock()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{}", error);
+                ExitCode::from(UNREADABLE_INPUT)
+            }
+        };
+    }
+
+    let source = options.file.clone().unwrap_or("<stdin>".to_string());
+    let input = match read_input(&options.file) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("{}: {}", source, error);
+            return ExitCode::from(UNREADABLE_INPUT);
+        }
+    };
+    let (allies, enemies) = match read_fighters(&input) {
+        Ok(teams) => teams,
+        Err(error) => {
+            eprintln!("{}:{}", source, error);
+            return ExitCode::from(INVALID_INPUT);
+        }
+    };
+
+    match options.command {
+        Command::Check => check(&allies, &enemies, options.turns.unwrap_or(MAX_TURNS)),
+        Command::Run => run(allies, enemies, &options),
+        Command::Verify => verify_log(allies, enemies, &options),
+        Command::Serve => unreachable!(),
+    }
+}
+
+/// Reports the issues of every rule list, for fights of at most `max_turns` turns.
+fn check(allies: &[Fighter], enemies: &[Fighter], max_turns: u8) -> ExitCode {
+    let mut valid = true;
+    let mut letters = (b'A'..=b'Z').map(char::from);
+    for (index, fighter) in allies.iter().chain(enemies).enumerate() {
+        let Some(letter) = letters.next() else {
+            valid = false;
+            println!("fighter {} {}: {}: a description holds at most {} fighters, one per letter",
+                     index + 1, fighter.get_name(), Severity::Error, MAX_FIGHTERS);
+            continue;
+        };
+        for diagnostic in analyze_fighter(fighter, max_turns) {
+            valid &= diagnostic.severity != Severity::Error;
+            println!("[{}] {} rule {}: {}: {}",
+                     letter, fighter.get_name(), diagnostic.rule, diagnostic.severity, diagnostic.issue);
+        }
+    }
+    if valid { ExitCode::SUCCESS } else { ExitCode::from(INVALID_INPUT) }
+}
+
+fn build(allies: Vec<Fighter>, enemies: Vec<Fighter>, options: &Options) -> Fight {
+    let mut fight = Fight::build_fight(allies, enemies);
+    if let Some(turns) = options.turns {
+        fight = fight.with_max_turns(turns);
+    }
+    if let Some(seed) = options.seed {
+        fight = fight.with_seed(seed);
+    }
+    fight
+}
+
+/// Compares the log with the fight.
+fn verify_log(allies: Vec<Fighter>, enemies: Vec<Fighter>, options: &Options) -> ExitCode {
+    let log = options.log.as_ref().unwrap();
+    let fight_status = match read_input(&options.log) {
+        Ok(fight_status) => fight_status,
+        Err(error) => {
+            eprintln!("{}: {}", log, error);
+            return ExitCode::from(UNREADABLE_INPUT);
+        }
+    };
+    let replay = match read_replay(&fight_status) {
+        Ok(replay) => replay,
+        Err(error) => {
+            eprintln!("{}:{}", log, error);
+            return ExitCode::from(INVALID_INPUT);
+        }
+    };
+    let verdict = match verify_fight(&mut build(allies, enemies, options), &replay) {
+        Ok(verdict) => verdict,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::from(INVALID_INPUT);
+        }
+    };
+    match verdict {
+        Verdict::Match => {
+            println!("{}", verdict);
+            ExitCode::SUCCESS
+        }
+        Verdict::Diverged { .. } => {
+            println!("{}: {}", log, verdict);
+            ExitCode::FAILURE
+        }
     }
 }
+
+fn run(allies: Vec<Fighter>, enemies: Vec<Fighter>, options: &Options) -> ExitCode {
+    let mut fight = build(allies, enemies, options);
+
+    let state = match options.format {
+        Format::Status => {
+            let mut writer = match StatusWriter::new(&fight) {
+                Ok(writer) => writer,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return ExitCode::from(INVALID_INPUT);
+                }
+            };
+            let state = fight.run(&mut writer);
+            print!("{}", writer.into_string());
+            state
+        }
+        Format::Narrative => {
+            let mut narrator = Narrator::new(&fight);
+            fight.run(&mut narrator)
+        }
+        Format::Json => {
+            let mut events: Vec<FightEvent> = Vec::new();
+            let state = fight.run(&mut events);
+            println!("{}", json!({ "outcome": state, "events": events }));
+            state
+        }
+    };
+
+    ExitCode::from(match state {
+        State::AlliesVictory => 0,
+        State::EnemiesVictory => 1,
+        State::Draw => 2,
+    })
+}
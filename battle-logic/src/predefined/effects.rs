@@ -4,7 +4,12 @@ pub enum Effect {
     Attack {
         damage: u16,
         element: Element,
-    }
+    },
+    Inflict {
+        status: Status,
+        duration: u8,
+        potency: u16,
+    },
 }
 
 impl Effect {
@@ -13,6 +18,9 @@ impl Effect {
             Effect::Attack { damage, element } => {
                 Consequence::from_damage(element, *damage, user)
             }
+            Effect::Inflict { status, duration, potency } => {
+                Consequence::Inflict { status: *status, duration: *duration, potency: *potency }
+            }
         }
     }
 }
\ No newline at end of file
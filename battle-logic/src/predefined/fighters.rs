@@ -8,6 +8,7 @@ pub enum Fighters {
 }
 
 impl Fighters {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(self) -> Fighter {
         get(self)
     }
@@ -19,13 +20,15 @@ fn get(fighter: Fighters) -> Fighter {
             "Arches".to_string(),
             Stats::new(100, 5, 10, 10, 0, 10),
             vec![Rules::Attack2.new()],
-            Some(Box::new(WoodenSword.new())),
+            vec![Box::new(WoodenSword.new())],
+            None,
         ),
         Fighters::Bat => Fighter::new(
             "Bat".to_string(),
             Stats::new(60, 8, 15, 5, 8, 4),
             vec![Rules::Attack2.new()],
-            Some(Box::new(WoodenSword.new())),
+            vec![Box::new(WoodenSword.new())],
+            None,
         )
     }
 }
@@ -1,11 +1,15 @@
 pub mod fighters;
+pub mod relics;
 pub mod rules;
+pub mod spells;
 pub mod weapons;
 pub mod effects;
 
 pub mod prelude {
     pub use crate::predefined::fighters::*;
+    pub use crate::predefined::relics::*;
     pub use crate::predefined::rules::*;
+    pub use crate::predefined::spells::*;
     pub use crate::predefined::weapons::*;
     pub use crate::predefined::effects::*;
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+use crate::logic::prelude::*;
+
+pub struct Charm {
+    name: String,
+    trigger: fn(&Trigger) -> bool,
+    action: Action,
+}
+
+impl Relic for Charm {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn react(&self, trigger: &Trigger) -> Option<Action> {
+        if (self.trigger)(trigger) { Some(self.action.clone()) } else { None }
+    }
+}
+
+pub enum Relics {
+    /// Counter-attacks the strongest foe with a level 1 sword when hurt.
+    Thorns,
+    /// Defends when an ally dies.
+    Amulet,
+    /// Defends at the start of each turn.
+    Aegis,
+}
+
+impl Relics {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(self) -> Charm {
+        get(self)
+    }
+
+    /// Finds a relic by its name in the fighters_info format, e.g. `THORNS`.
+    pub fn from_name(name: &str) -> Option<Relics> {
+        match name {
+            "THORNS" => Some(Relics::Thorns),
+            "AMULET" => Some(Relics::Amulet),
+            "AEGIS" => Some(Relics::Aegis),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the predefined relic named `name` in the fighters_info format.
+pub fn get_relic(name: &str) -> Option<Box<dyn Relic>> {
+    Relics::from_name(name).map(|r| Box::new(r.new()) as Box<dyn Relic>)
+}
+
+fn get(name: Relics) -> Charm {
+    match name {
+        Relics::Thorns => Charm {
+            name: String::from("Thorns"),
+            trigger: |t| matches!(t, Trigger::TookDamage { .. }),
+            action: Action::Attack(Target::FoeMost(Stat::Attack), WeaponRef::new(WeaponKind::Sword, 1)),
+        },
+        Relics::Amulet => Charm {
+            name: String::from("Amulet"),
+            trigger: |t| *t == Trigger::AllyDied,
+            action: Action::Defense,
+        },
+        Relics::Aegis => Charm {
+            name: String::from("Aegis"),
+            trigger: |t| *t == Trigger::TurnStarted,
+            action: Action::Defense,
+        },
+    }
+}
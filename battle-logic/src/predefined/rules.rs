@@ -8,6 +8,7 @@ pub enum Rules {
 }
 
 impl Rules {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(self) -> Rule {
         get(self)
     }
@@ -25,10 +26,10 @@ fn get(name: Rules) -> Rule {
         },
         Rules::Attack2 => Rule {
             gate: Gate::ID(Condition::EveryXTurn(2)),
-            action: Action::Attack(Target::FoeLess(Stat::Health)),
+            action: Action::Attack(Target::FoeLess(Stat::Health), WeaponRef::new(WeaponKind::Sword, 1)),
         },
         Rules::Careful => Rule {
-            gate: Gate::AND(
+            gate: Gate::and(
                 Condition::EveryXTurn(2),
                 Condition::LessXHP(30, Target::Them)
             ),
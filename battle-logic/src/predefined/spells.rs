@@ -0,0 +1,92 @@
+use crate::logic::prelude::*;
+use crate::predefined::prelude::*;
+
+pub struct Fireball {
+    name: String,
+    level: u8,
+    effects: Vec<(WeaponTarget, Effect)>,
+}
+
+impl Spell for Fireball {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_kind(&self) -> SpellKind {
+        SpellKind::Fireball
+    }
+
+    fn get_level(&self) -> u8 {
+        self.level
+    }
+
+    fn cast(&self, user: &Fighter, target: &Fighter) -> Vec<(WeaponTarget, Consequence)> {
+        let mut consequences = vec![];
+        for (b, e) in &self.effects {
+            consequences.push((b.clone(), e.to_consequence(user, target)))
+        }
+        consequences
+    }
+}
+
+pub enum Fireballs {
+    Fireball,
+    GreatFireball,
+}
+
+impl Fireballs {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(self) -> Fireball {
+        get(self)
+    }
+
+    pub fn from_level(level: u8) -> Option<Fireballs> {
+        match level {
+            1 => Some(Fireballs::Fireball),
+            2 => Some(Fireballs::GreatFireball),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the predefined spell of the given kind and level.
+pub fn get_spell(kind: SpellKind, level: u8) -> Option<Box<dyn Spell>> {
+    match kind {
+        SpellKind::Fireball => Fireballs::from_level(level).map(|s| Box::new(s.new()) as Box<dyn Spell>),
+    }
+}
+
+fn get(name: Fireballs) -> Fireball {
+    match name {
+        Fireballs::Fireball => Fireball {
+            name: String::from("Fireball"),
+            level: 1,
+            effects: vec![
+                (WeaponTarget::Other, Effect::Attack {
+                    damage: 8,
+                    element: Element::Demonic
+                }),
+                (WeaponTarget::Other, Effect::Inflict {
+                    status: Status::Burn,
+                    duration: 2,
+                    potency: 3,
+                }),
+            ],
+        },
+        Fireballs::GreatFireball => Fireball {
+            name: String::from("Great Fireball"),
+            level: 2,
+            effects: vec![
+                (WeaponTarget::Other, Effect::Attack {
+                    damage: 14,
+                    element: Element::Demonic
+                }),
+                (WeaponTarget::Other, Effect::Inflict {
+                    status: Status::Burn,
+                    duration: 3,
+                    potency: 5,
+                }),
+            ],
+        },
+    }
+}
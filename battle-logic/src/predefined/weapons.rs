@@ -3,6 +3,7 @@ use crate::predefined::prelude::*;
 
 pub struct Sword {
     name: String,
+    level: u8,
     damage: Vec<(WeaponTarget, Effect)>,
 }
 
@@ -11,6 +12,14 @@ impl Weapon for Sword {
         self.name.clone()
     }
 
+    fn get_kind(&self) -> WeaponKind {
+        WeaponKind::Sword
+    }
+
+    fn get_level(&self) -> u8 {
+        self.level
+    }
+
     fn use_weapon(&self, user: &Fighter, target: &Fighter) -> Vec<(WeaponTarget, Consequence)> {
         let mut consequences  = vec![];
         for (b, e) in &self.damage {
@@ -20,20 +29,41 @@ impl Weapon for Sword {
     }
 }
 
+#[allow(clippy::enum_variant_names)]
 pub enum Swords {
     WoodenSword,
+    IronSword,
+    VenomSword,
 }
 
 impl Swords {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(self) -> Sword {
         get(self)
     }
+
+    pub fn from_level(level: u8) -> Option<Swords> {
+        match level {
+            1 => Some(Swords::WoodenSword),
+            2 => Some(Swords::IronSword),
+            3 => Some(Swords::VenomSword),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the predefined weapon of the given kind and level.
+pub fn get_weapon(kind: WeaponKind, level: u8) -> Option<Box<dyn Weapon>> {
+    match kind {
+        WeaponKind::Sword => Swords::from_level(level).map(|s| Box::new(s.new()) as Box<dyn Weapon>),
+    }
 }
 
 fn get(name: Swords) -> Sword {
     match name {
         Swords::WoodenSword => Sword {
             name: String::from("Wooden Sword"),
+            level: 1,
             damage: vec![
                 (WeaponTarget::Other, Effect::Attack {
                     damage: 10,
@@ -41,5 +71,30 @@ fn get(name: Swords) -> Sword {
                 })
             ],
         },
+        Swords::IronSword => Sword {
+            name: String::from("Iron Sword"),
+            level: 2,
+            damage: vec![
+                (WeaponTarget::Other, Effect::Attack {
+                    damage: 15,
+                    element: Element::Neutral
+                })
+            ],
+        },
+        Swords::VenomSword => Sword {
+            name: String::from("Venom Sword"),
+            level: 3,
+            damage: vec![
+                (WeaponTarget::Other, Effect::Attack {
+                    damage: 8,
+                    element: Element::Natural
+                }),
+                (WeaponTarget::Other, Effect::Inflict {
+                    status: Status::Poisoned,
+                    duration: 3,
+                    potency: 4,
+                }),
+            ],
+        },
     }
 }
@@ -0,0 +1,55 @@
+/* Runs the fight described in the file given as argument through the C API.
+ * Prints the fight_status log and exits with the outcome. */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "battle_logic.h"
+
+static char *read_file(const char *path) {
+    FILE *file = fopen(path, "rb");
+    if (!file) return NULL;
+    fseek(file, 0, SEEK_END);
+    long size = ftell(file);
+    rewind(file);
+    char *text = malloc(size + 1);
+    size_t read = fread(text, 1, size, file);
+    text[read] = '\0';
+    fclose(file);
+    return text;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 2) return 100;
+    char *info = read_file(argv[1]);
+    if (!info) return 101;
+
+    char *error = NULL;
+    BlFight *fight = bl_fight_new(info, &error);
+    free(info);
+    if (!fight) {
+        fprintf(stderr, "%s\n", error);
+        bl_string_free(error);
+        return 102;
+    }
+
+    /* One action at a time, as a game would animate it. */
+    BlOutcome outcome;
+    while ((outcome = bl_fight_step(fight)) == BL_OUTCOME_ONGOING) {}
+    if (bl_fight_run(fight) != outcome) return 103;
+
+    BlEvent event;
+    size_t count = bl_fight_event_count(fight);
+    if (count == 0 || !bl_fight_event(fight, count - 1, &event)) return 104;
+    if (event.kind != BL_EVENT_KIND_OUTCOME || event.number != (uint32_t) outcome) return 105;
+    if (bl_fight_event(fight, count, &event)) return 106;
+
+    char *text = bl_fight_events_text(fight);
+    printf("%s", text);
+    bl_string_free(text);
+
+    bl_fight_clear_events(fight);
+    if (bl_fight_event_count(fight) != 0) return 107;
+    bl_fight_free(fight);
+    return outcome;
+}
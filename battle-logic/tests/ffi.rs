@@ -0,0 +1,67 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use battle_logic::prelude::*;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+/// The committed header must match the C API.
+#[test]
+fn test_header() {
+    let config = cbindgen::Config::from_file(Path::new(MANIFEST_DIR).join("cbindgen.toml")).unwrap();
+    let mut header = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(MANIFEST_DIR)
+        .with_config(config)
+        .generate()
+        .unwrap()
+        .write(&mut header);
+    let header = String::from_utf8(header).unwrap();
+    if header != fs::read_to_string(Path::new(MANIFEST_DIR).join("include/battle_logic.h")).unwrap() {
+        let generated = Path::new(env!("CARGO_TARGET_TMPDIR")).join("battle_logic.h");
+        fs::write(&generated, header).unwrap();
+        panic!("include/battle_logic.h is outdated, see {}", generated.display());
+    }
+}
+
+/// Directory holding the C library, next to this test.
+fn library_dir() -> PathBuf {
+    env::current_exe().unwrap().parent().unwrap().to_path_buf()
+}
+
+#[cfg(unix)]
+#[test]
+fn test_c_program() {
+    let allies = vec![Fighters::Arches.new()];
+    let enemies = vec![Fighters::Bat.new()];
+    let info = write_fighters(&allies, &enemies).unwrap();
+    let expected = simulate(&info).unwrap();
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let info_path = dir.join("arches_vs_bat.txt");
+    let program = dir.join("arches_vs_bat");
+    fs::write(&info_path, &info).unwrap();
+
+    let library_dir = library_dir();
+    let status = Command::new(env::var("CC").unwrap_or("cc".to_string()))
+        .arg(Path::new(MANIFEST_DIR).join("tests/c/arches_vs_bat.c"))
+        .arg("-I").arg(Path::new(MANIFEST_DIR).join("include"))
+        .arg("-L").arg(&library_dir)
+        .arg(format!("-Wl,-rpath,{}", library_dir.display()))
+        .args(["-lbattle_logic", "-Wall", "-Werror", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(&program).arg(&info_path).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    let outcome = Fight::build_fight(allies, enemies).run(&mut Vec::new());
+    assert_eq!(output.status.code(), Some(match outcome {
+        State::AlliesVictory => 1,
+        State::EnemiesVictory => 2,
+        State::Draw => 3,
+    }));
+}